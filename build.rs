@@ -0,0 +1,17 @@
+fn main() {
+    // the C header is committed, so cbindgen is only needed after changing the C interface
+    #[cfg(feature = "header")]
+    {
+        println!("cargo:rerun-if-changed=src/capi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml"))
+            .expect("Could not read cbindgen.toml");
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(format!("{crate_dir}/src/capi.rs"))
+            .generate()
+            .expect("Could not generate the C header")
+            .write_to_file(format!("{crate_dir}/include/radio_datetime_analyzer.h"));
+    }
+}
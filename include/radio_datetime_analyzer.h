@@ -0,0 +1,110 @@
+#ifndef RADIO_DATETIME_ANALYZER_H
+#define RADIO_DATETIME_ANALYZER_H
+
+/* Generated by cbindgen from src/capi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Station number of DCF77 for `rda_analyzer_new()`.
+#define RDA_STATION_DCF77 0
+
+// Station number of MSF for `rda_analyzer_new()`.
+#define RDA_STATION_MSF 1
+
+// Value of the fields of `RdaMinute` which could not be decoded.
+#define RDA_UNKNOWN INT16_MIN
+
+// An analyzer of the minutes of a station, opaque to C.
+//
+// It is fed the bits of a log as they arrive, in the same format as the log files of the
+// command line, and returns the analysis of each complete minute. The C header
+// `include/radio_datetime_analyzer.h` is generated from this module with
+// `cargo build --features header`.
+typedef struct RdaAnalyzer RdaAnalyzer;
+
+// The analysis of one minute. Fields which could not be decoded are `RDA_UNKNOWN`.
+typedef struct RdaMinute {
+  // 0-based index of the minute (line) in the log
+  size_t index;
+  // if the minute was decoded without any problems
+  bool good;
+  // year within the century, 0-99
+  int16_t year;
+  int16_t month;
+  int16_t day;
+  // day of the week, as broadcast by the station
+  int16_t weekday;
+  int16_t hour;
+  int16_t minute;
+  // state of daylight saving time, see radio_datetime_utils::set_dst()
+  int16_t dst;
+  // state of the leap second, see radio_datetime_utils::set_leap_second()
+  int16_t leap_second;
+  // DUT1 in tenths of a second, MSF only
+  int16_t dut1;
+  // number of problems found in the minute
+  size_t error_count;
+  // the bits of the minute as logged, valid until the next call for this analyzer
+  const char *bits;
+  // the problems found in the minute, each followed by a newline, valid until the next call
+  // for this analyzer
+  const char *errors;
+} RdaMinute;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Return a new analyzer for the given station, or NULL if the station is unknown. It must be
+// freed with `rda_analyzer_free()`.
+//
+// # Arguments
+// * `station` - `RDA_STATION_DCF77` or `RDA_STATION_MSF`
+struct RdaAnalyzer *rda_analyzer_new(int station);
+
+// Free the given analyzer, NULL is ignored.
+//
+// # Safety
+// `analyzer` must be NULL or returned by `rda_analyzer_new()` and not freed yet.
+void rda_analyzer_free(struct RdaAnalyzer *analyzer);
+
+// Use the given table of leap seconds, in the format of `leap-seconds.list`, instead of the
+// built-in one. Return 0 on success or -1 if the table is invalid. It should be called before
+// the log is fed, as the analysis starts over with the new table.
+//
+// # Safety
+// `analyzer` must be a valid analyzer and `leap_seconds_list` a NUL-terminated string.
+int rda_analyzer_set_leap_seconds(struct RdaAnalyzer *analyzer, const char *leap_seconds_list);
+
+// Take decoded years within the century from the given one on to be in the 1900s instead of
+// the 2000s, e.g. 90 for logs from the 1990s. The default of 100 puts all years in the 2000s.
+// Return 0 on success or -1 if the pivot is not in 0-100. It should be called before the log
+// is fed, as the analysis starts over with the new pivot.
+//
+// # Safety
+// `analyzer` must be a valid analyzer.
+int rda_analyzer_set_century_pivot(struct RdaAnalyzer *analyzer, int century_pivot);
+
+// Add the given bytes to the log of the analyzer and analyze the minutes they complete. Return
+// 0 on success or -1 for NULL pointers.
+//
+// # Safety
+// `analyzer` must be a valid analyzer and `data` point to `length` bytes.
+int rda_analyzer_feed(struct RdaAnalyzer *analyzer, const uint8_t *data, size_t length);
+
+// Store the analysis of the next complete minute of the log in `minute`. Return 1 if a minute
+// was stored, 0 if there is no new complete minute yet or -1 for NULL pointers.
+//
+// # Safety
+// `analyzer` must be a valid analyzer and `minute` point to writable memory for an `RdaMinute`.
+int rda_analyzer_next(struct RdaAnalyzer *analyzer, struct RdaMinute *minute);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RADIO_DATETIME_ANALYZER_H */
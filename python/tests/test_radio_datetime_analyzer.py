@@ -0,0 +1,74 @@
+"""Tests of the Python module against the sample logs.
+
+Build and install the module with `maturin develop --features python`, then run
+`python -m unittest discover python/tests` from the top of the repository.
+"""
+
+import pathlib
+import unittest
+
+import radio_datetime_analyzer
+
+SAMPLES = pathlib.Path(__file__).parents[2] / "src" / "transmitters" / "test"
+
+
+def sample(station):
+    """Return the sample log of the given station."""
+    return (SAMPLES / f"sample_{station}.log").read_text()
+
+
+class TestAnalyzeBuffer(unittest.TestCase):
+    def test_dcf77(self):
+        minutes = radio_datetime_analyzer.analyze_buffer("dcf77", sample("dcf77"))
+        self.assertEqual(len(minutes), sample("dcf77").count("\n"))
+        first = minutes[0]
+        self.assertEqual(first["index"], 0)
+        self.assertEqual(first["bits"], "00000000000000000010100011011110001110001110101001100110011")
+        self.assertEqual(
+            (first["year"], first["month"], first["day"], first["hour"], first["minute"]),
+            (99, 12, 31, 23, 58),
+        )
+        self.assertTrue(first["good"])
+        self.assertEqual(first["errors"], [])
+        self.assertIsNone(first["dut1"])
+        short = minutes[4]
+        self.assertFalse(short["good"])
+        self.assertIsNone(short["year"])
+        self.assertEqual(short["errors"], ["Minute is 1 seconds instead of 60 seconds long"])
+
+    def test_msf(self):
+        minutes = radio_datetime_analyzer.analyze_buffer("MSF", sample("msf"))
+        self.assertEqual(len(minutes), sample("msf").count("\n"))
+        self.assertTrue(any(minute["dut1"] is not None for minute in minutes))
+
+    def test_century_pivot(self):
+        log = sample("dcf77")
+        first = radio_datetime_analyzer.analyze_buffer("dcf77", log)[0]
+        self.assertGreater(first["utc"], 946684800)
+        first = radio_datetime_analyzer.analyze_buffer("dcf77", log, century_pivot=90)[0]
+        self.assertLess(first["utc"], 946684800)
+
+    def test_invalid(self):
+        with self.assertRaises(ValueError):
+            radio_datetime_analyzer.analyze_buffer("wwvb", "")
+        with self.assertRaises(ValueError):
+            radio_datetime_analyzer.analyze_buffer("dcf77", "", leap_seconds="invalid")
+        with self.assertRaises(ValueError):
+            radio_datetime_analyzer.analyze_buffer("dcf77", "", century_pivot=101)
+
+
+class TestAnalyzer(unittest.TestCase):
+    def test_feed(self):
+        log = sample("dcf77")
+        analyzer = radio_datetime_analyzer.Analyzer("dcf77")
+        minutes = []
+        # feed the log in pieces which do not end at the minute markers
+        for start in range(0, len(log), 25):
+            minutes.extend(analyzer.feed(log[start:start + 25]))
+        self.assertEqual(minutes, radio_datetime_analyzer.analyze_buffer("dcf77", log))
+        self.assertEqual(analyzer.feed("0101"), [])
+        self.assertEqual(len(analyzer.feed("\n")), 1)
+
+
+if __name__ == "__main__":
+    unittest.main()
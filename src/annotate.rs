@@ -0,0 +1,124 @@
+use crate::transmitters::{self, Field, FieldRole, MinuteReport};
+use crate::Station;
+
+/// Return the output lines of the given minute with the bits shown as a table instead, with
+/// the name of each bit and the value it adds to its field.
+///
+/// # Arguments
+/// * `station` - the station which was logged
+/// * `report` - the analysis of the minute
+pub fn str_annotated(station: Station, report: &MinuteReport) -> Vec<String> {
+    let layout = transmitters::layout(station);
+    let minute_length = report.bits.chars().count();
+    let mut res = vec![format!(
+        "{:>6} {:<3} {:<41} {}\n",
+        "Second", "Bit", "Name", "Value"
+    )];
+    for (second, c) in report.bits.chars().enumerate() {
+        let (name, value) = match layout.field_at(second, minute_length) {
+            Some(s_field) => {
+                let bit = layout.nominal_second(second, minute_length) - s_field.start;
+                (s_field.bit_name(bit), str_value(station, s_field, bit, c))
+            }
+            None => (String::from("?"), String::from("")),
+        };
+        res.push(
+            format!("{second:>6} {c:<3} {name:<41} {value}")
+                .trim_end()
+                .to_string()
+                + "\n",
+        );
+    }
+    let bits_line = report.bits_line();
+    res.extend(
+        report
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|&(idx, _)| Some(idx) != bits_line)
+            .map(|(_, line)| line.clone()),
+    );
+    res
+}
+
+/// Return the value which the given bit adds to its field, ? if it is undetermined or nothing
+/// if the field is not a number.
+///
+/// # Arguments
+/// * `station` - the station which was logged
+/// * `field` - the field the bit belongs to
+/// * `bit` - the position of the bit within the field, 0-based
+/// * `c` - the bit as logged
+fn str_value(station: Station, field: &Field, bit: usize, c: char) -> String {
+    let Some(weight) = field.weights.get(bit) else {
+        return String::from("");
+    };
+    let is_set = match (station, field.role) {
+        (Station::Dcf77, _) => c == '1',
+        // DUT1 is in the B bits, the date and time in the A bits
+        (Station::Msf, FieldRole::Data) => c == '2' || c == '3',
+        (Station::Msf, _) => c == '1' || c == '3',
+    };
+    if c == '_' {
+        String::from("?")
+    } else if is_set {
+        weight.to_string()
+    } else {
+        String::from("0")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_str_annotated_dcf77() {
+        let report = MinuteReport {
+            bits: String::from("00000000000000000010100011011110001110001110101001100110011"),
+            lines: vec![
+                String::from("0 00000000000000 0 001 0 1 ...\n"),
+                String::from("first_minute=true seconds=59\n"),
+                String::from("\n"),
+            ],
+            ..MinuteReport::default()
+        };
+        let res = str_annotated(Station::Dcf77, &report);
+        assert_eq!(res.len(), 1 + 59 + 2);
+        assert_eq!(res[1], "     0 0   Start of minute\n");
+        assert_eq!(res[2], "     1 0   Third-party 1\n");
+        assert_eq!(res[19], "    18 1   Z2 (CET)\n");
+        assert_eq!(
+            res[25],
+            "    24 1   Minute BCD 8                              8\n"
+        );
+        assert_eq!(
+            res[27],
+            "    26 0   Minute BCD 20                             0\n"
+        );
+        assert_eq!(res[36], "    35 1   P2\n");
+        assert_eq!(res[60], "first_minute=true seconds=59\n");
+        assert_eq!(res[61], "\n");
+    }
+
+    #[test]
+    fn test_str_annotated_msf() {
+        let report = MinuteReport {
+            bits: String::from("400000000220000000010000000011101000110100011101100101133110"),
+            ..MinuteReport::default()
+        };
+        let res = str_annotated(Station::Msf, &report);
+        assert_eq!(
+            res[11],
+            "    10 2   DUT1- 2                                   -1\n"
+        );
+        assert_eq!(
+            res[20],
+            "    19 1   Year BCD 20                               20\n"
+        );
+        assert_eq!(
+            res[56],
+            "    55 3   End-of-minute 1, B: Month/day-of-month parity\n"
+        );
+    }
+}
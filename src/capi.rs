@@ -0,0 +1,308 @@
+use crate::leap_seconds::LeapSecondTable;
+use crate::transmitters::{self, AnalysisSettings, MinuteReport};
+use crate::Station;
+use std::collections::VecDeque;
+use std::ffi::{c_char, c_int, CStr, CString};
+
+/// Station number of DCF77 for `rda_analyzer_new()`.
+pub const RDA_STATION_DCF77: c_int = 0;
+/// Station number of MSF for `rda_analyzer_new()`.
+pub const RDA_STATION_MSF: c_int = 1;
+/// Value of the fields of `RdaMinute` which could not be decoded.
+pub const RDA_UNKNOWN: i16 = i16::MIN;
+
+/// An analyzer of the minutes of a station, opaque to C.
+///
+/// It is fed the bits of a log as they arrive, in the same format as the log files of the
+/// command line, and returns the analysis of each complete minute. The C header
+/// `include/radio_datetime_analyzer.h` is generated from this module with
+/// `cargo build --features header`.
+pub struct RdaAnalyzer {
+    station: Station,
+    settings: AnalysisSettings,
+    analyzer: transmitters::Analyzer,
+    /// the minutes which are complete but not returned by `rda_analyzer_next()` yet
+    minutes: VecDeque<MinuteReport>,
+    /// the bits and problems of the last returned minute, which `RdaMinute` points to
+    bits: CString,
+    errors: CString,
+}
+
+impl RdaAnalyzer {
+    /// Return a new analyzer for the given station, at the start of a log.
+    ///
+    /// # Arguments
+    /// * `station` - the station which is logged
+    /// * `settings` - the settings of the analysis
+    fn new(station: Station, settings: &AnalysisSettings) -> Self {
+        Self {
+            station,
+            settings: settings.clone(),
+            analyzer: transmitters::Analyzer::new(station, settings),
+            minutes: VecDeque::new(),
+            bits: CString::default(),
+            errors: CString::default(),
+        }
+    }
+
+    /// Use the given settings, which starts the analysis over at the start of a log. The
+    /// minutes which are complete already are kept.
+    ///
+    /// # Arguments
+    /// * `settings` - the settings of the analysis
+    fn set_settings(&mut self, settings: AnalysisSettings) {
+        self.analyzer = transmitters::Analyzer::new(self.station, &settings);
+        self.settings = settings;
+    }
+
+    /// Analyze the given part of the log and queue each minute which is completed by it.
+    ///
+    /// # Arguments
+    /// * `buffer` - the next part of the log
+    fn feed(&mut self, buffer: &str) {
+        self.minutes.extend(self.analyzer.feed(buffer));
+    }
+
+    /// Return the analysis of the next complete minute, or None if there is no new one yet.
+    fn next_minute(&mut self) -> Option<MinuteReport> {
+        self.minutes.pop_front()
+    }
+}
+
+/// The analysis of one minute. Fields which could not be decoded are `RDA_UNKNOWN`.
+#[repr(C)]
+pub struct RdaMinute {
+    /// 0-based index of the minute (line) in the log
+    pub index: usize,
+    /// if the minute was decoded without any problems
+    pub good: bool,
+    /// year within the century, 0-99
+    pub year: i16,
+    pub month: i16,
+    pub day: i16,
+    /// day of the week, as broadcast by the station
+    pub weekday: i16,
+    pub hour: i16,
+    pub minute: i16,
+    /// state of daylight saving time, see radio_datetime_utils::set_dst()
+    pub dst: i16,
+    /// state of the leap second, see radio_datetime_utils::set_leap_second()
+    pub leap_second: i16,
+    /// DUT1 in tenths of a second, MSF only
+    pub dut1: i16,
+    /// number of problems found in the minute
+    pub error_count: usize,
+    /// the bits of the minute as logged, valid until the next call for this analyzer
+    pub bits: *const c_char,
+    /// the problems found in the minute, each followed by a newline, valid until the next call
+    /// for this analyzer
+    pub errors: *const c_char,
+}
+
+/// Return a new analyzer for the given station, or NULL if the station is unknown. It must be
+/// freed with `rda_analyzer_free()`.
+///
+/// # Arguments
+/// * `station` - `RDA_STATION_DCF77` or `RDA_STATION_MSF`
+#[no_mangle]
+pub extern "C" fn rda_analyzer_new(station: c_int) -> *mut RdaAnalyzer {
+    let station = match station {
+        RDA_STATION_DCF77 => Station::Dcf77,
+        RDA_STATION_MSF => Station::Msf,
+        _ => return std::ptr::null_mut(),
+    };
+    Box::into_raw(Box::new(RdaAnalyzer::new(
+        station,
+        &AnalysisSettings::default(),
+    )))
+}
+
+/// Free the given analyzer, NULL is ignored.
+///
+/// # Safety
+/// `analyzer` must be NULL or returned by `rda_analyzer_new()` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn rda_analyzer_free(analyzer: *mut RdaAnalyzer) {
+    if !analyzer.is_null() {
+        drop(Box::from_raw(analyzer));
+    }
+}
+
+/// Use the given table of leap seconds, in the format of `leap-seconds.list`, instead of the
+/// built-in one. Return 0 on success or -1 if the table is invalid. It should be called before
+/// the log is fed, as the analysis starts over with the new table.
+///
+/// # Safety
+/// `analyzer` must be a valid analyzer and `leap_seconds_list` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rda_analyzer_set_leap_seconds(
+    analyzer: *mut RdaAnalyzer,
+    leap_seconds_list: *const c_char,
+) -> c_int {
+    let (Some(analyzer), false) = (analyzer.as_mut(), leap_seconds_list.is_null()) else {
+        return -1;
+    };
+    let text = CStr::from_ptr(leap_seconds_list).to_string_lossy();
+    match LeapSecondTable::from_leap_seconds_list(&text) {
+        Ok(s_table) => {
+            analyzer.set_settings(AnalysisSettings {
+                leap_seconds: s_table,
+                ..analyzer.settings.clone()
+            });
+            0
+        }
+        Err(_) => -1,
+    }
+}
+
+/// Take decoded years within the century from the given one on to be in the 1900s instead of
+/// the 2000s, e.g. 90 for logs from the 1990s. The default of 100 puts all years in the 2000s.
+/// Return 0 on success or -1 if the pivot is not in 0-100. It should be called before the log
+/// is fed, as the analysis starts over with the new pivot.
+///
+/// # Safety
+/// `analyzer` must be a valid analyzer.
+#[no_mangle]
+pub unsafe extern "C" fn rda_analyzer_set_century_pivot(
+    analyzer: *mut RdaAnalyzer,
+    century_pivot: c_int,
+) -> c_int {
+    let (Some(analyzer), Ok(century_pivot @ 0..=100)) =
+        (analyzer.as_mut(), u8::try_from(century_pivot))
+    else {
+        return -1;
+    };
+    analyzer.set_settings(AnalysisSettings {
+        century_pivot,
+        ..analyzer.settings.clone()
+    });
+    0
+}
+
+/// Add the given bytes to the log of the analyzer and analyze the minutes they complete. Return
+/// 0 on success or -1 for NULL pointers.
+///
+/// # Safety
+/// `analyzer` must be a valid analyzer and `data` point to `length` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rda_analyzer_feed(
+    analyzer: *mut RdaAnalyzer,
+    data: *const u8,
+    length: usize,
+) -> c_int {
+    let (Some(analyzer), false) = (analyzer.as_mut(), data.is_null()) else {
+        return -1;
+    };
+    analyzer.feed(&String::from_utf8_lossy(std::slice::from_raw_parts(
+        data, length,
+    )));
+    0
+}
+
+/// Store the analysis of the next complete minute of the log in `minute`. Return 1 if a minute
+/// was stored, 0 if there is no new complete minute yet or -1 for NULL pointers.
+///
+/// # Safety
+/// `analyzer` must be a valid analyzer and `minute` point to writable memory for an `RdaMinute`.
+#[no_mangle]
+pub unsafe extern "C" fn rda_analyzer_next(
+    analyzer: *mut RdaAnalyzer,
+    minute: *mut RdaMinute,
+) -> c_int {
+    let (Some(analyzer), false) = (analyzer.as_mut(), minute.is_null()) else {
+        return -1;
+    };
+    let Some(report) = analyzer.next_minute() else {
+        return 0;
+    };
+    analyzer.bits = to_c_string(&report.bits);
+    analyzer.errors = to_c_string(
+        &report
+            .errors
+            .iter()
+            .map(|error| format!("{error}\n"))
+            .collect::<String>(),
+    );
+    minute.write(to_minute(
+        analyzer.station,
+        &report,
+        &analyzer.bits,
+        &analyzer.errors,
+    ));
+    1
+}
+
+/// Return the given minute as `RdaMinute` pointing to the given strings.
+///
+/// # Arguments
+/// * `station` - the station which was received
+/// * `report` - the analysis of the minute
+/// * `bits` - the bits of the minute
+/// * `errors` - the problems of the minute
+fn to_minute(station: Station, report: &MinuteReport, bits: &CStr, errors: &CStr) -> RdaMinute {
+    // the UTC time does not fit into the fields of RdaMinute
+    let [year, month, day, weekday, hour, minute, dst, leap_second, dut1, _] = report
+        .fields(station)
+        .map(|(_, value)| value.map_or(RDA_UNKNOWN, |v| v as i16));
+    RdaMinute {
+        index: report.index,
+        good: report.is_good(),
+        year,
+        month,
+        day,
+        weekday,
+        hour,
+        minute,
+        dst,
+        leap_second,
+        dut1,
+        error_count: report.errors.len(),
+        bits: bits.as_ptr(),
+        errors: errors.as_ptr(),
+    }
+}
+
+/// Return the given text as C string, without any NUL bytes.
+///
+/// # Arguments
+/// * `text` - the text to convert
+fn to_c_string(text: &str) -> CString {
+    CString::new(text.replace('\0', "")).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::MaybeUninit;
+
+    #[test]
+    fn test_analyzer() {
+        assert!(rda_analyzer_new(2).is_null());
+        let analyzer = rda_analyzer_new(RDA_STATION_DCF77);
+        let mut minute = MaybeUninit::<RdaMinute>::uninit();
+        // SAFETY: the analyzer is valid until it is freed at the end
+        unsafe {
+            assert_eq!(rda_analyzer_next(analyzer, minute.as_mut_ptr()), 0);
+            assert_eq!(rda_analyzer_feed(analyzer, b"0101\n01".as_ptr(), 7), 0);
+            assert_eq!(rda_analyzer_next(analyzer, minute.as_mut_ptr()), 1);
+            let first = minute.assume_init_ref();
+            assert_eq!(first.index, 0);
+            assert!(!first.good);
+            assert_eq!(first.year, RDA_UNKNOWN);
+            assert_eq!(CStr::from_ptr(first.bits).to_str(), Ok("0101"));
+            assert!(first.error_count > 0);
+            assert!(CStr::from_ptr(first.errors)
+                .to_string_lossy()
+                .ends_with('\n'));
+            assert_eq!(rda_analyzer_next(analyzer, minute.as_mut_ptr()), 0);
+            assert_eq!(rda_analyzer_feed(analyzer, std::ptr::null(), 0), -1);
+            assert_eq!(
+                rda_analyzer_set_leap_seconds(analyzer, c"invalid".as_ptr()),
+                -1
+            );
+            assert_eq!(rda_analyzer_set_century_pivot(analyzer, 101), -1);
+            assert_eq!(rda_analyzer_set_century_pivot(analyzer, 90), 0);
+            rda_analyzer_free(analyzer);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::transmitters::{self, FieldRole, MinuteReport};
+use crate::Station;
+
+const RESET: &str = "\x1b[0m";
+const RED: &str = "\x1b[31m";
+/// white on red, for the bits of a failed parity check
+const FAILED_PARITY: &str = "\x1b[97;41m";
+const MISSING: &str = "\x1b[35m";
+
+/// Return the ANSI color of the bits of the given role.
+///
+/// # Arguments
+/// * `role` - the role of the field the bits belong to
+fn role_color(role: FieldRole) -> &'static str {
+    match role {
+        FieldRole::Marker => "\x1b[90m",
+        FieldRole::Flag => "\x1b[33m",
+        FieldRole::Data => "\x1b[34m",
+        FieldRole::Time => "\x1b[32m",
+        FieldRole::Parity => "\x1b[36m",
+    }
+}
+
+/// Return the output lines of the given minute with ANSI colors: the bits are colored by the
+/// role of their field, the bits of failed parity checks are highlighted and problems are red.
+///
+/// # Arguments
+/// * `station` - the station which was logged
+/// * `report` - the analysis of the minute
+pub fn colorize(station: Station, report: &MinuteReport) -> Vec<String> {
+    let bits_line = report.bits_line();
+    report
+        .lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            if Some(idx) == bits_line {
+                colorize_bits(station, report, line)
+            } else if report.errors.iter().any(|error| error.line == Some(idx)) {
+                format!("{RED}{}{RESET}\n", line.trim_end())
+            } else {
+                line.clone()
+            }
+        })
+        .collect()
+}
+
+/// Return the given line of grouped bits with ANSI colors.
+///
+/// # Arguments
+/// * `station` - the station which was logged
+/// * `report` - the analysis of the minute
+/// * `line` - the bits of the minute grouped by spaces, as shown in the output
+fn colorize_bits(station: Station, report: &MinuteReport, line: &str) -> String {
+    let layout = transmitters::layout(station);
+    let minute_length = report.bits.chars().count();
+    let failed: Vec<_> = report
+        .errors
+        .iter()
+        .filter_map(|error| layout.parities.get(error.parity?))
+        .collect();
+    let mut res = String::from("");
+    let mut second = 0;
+    for c in line.chars() {
+        if c == ' ' || c == '\n' {
+            res.push(c);
+            continue;
+        }
+        let nominal = layout.nominal_second(second, minute_length);
+        let color = if failed.iter().any(|parity| {
+            (parity.start..parity.end).contains(&nominal) || parity.parity_bit == nominal
+        }) {
+            FAILED_PARITY
+        } else if c == '_' {
+            MISSING
+        } else {
+            layout.role_at(second, minute_length).map_or("", role_color)
+        };
+        res += &format!("{color}{c}{RESET}");
+        second += 1;
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::{dcf77, ErrorKind, MinuteError};
+
+    #[test]
+    fn test_colorize() {
+        let report = MinuteReport {
+            bits: String::from("0000000000000000001010000001"),
+            lines: vec![
+                String::from("increase_second() == false\n"),
+                String::from("0 00000000000000 0 001 0 1 0000001\n"),
+                String::from("Third-party buffer=0x0000\n"),
+                String::from("Minute parity bad\n"),
+                String::from("\n"),
+            ],
+            errors: vec![
+                MinuteError {
+                    line: Some(0),
+                    ..MinuteError::new(ErrorKind::Length, "increase_second() == false")
+                },
+                MinuteError {
+                    line: Some(3),
+                    ..MinuteError::bad_parity(0, &dcf77::LAYOUT.parities[0])
+                },
+            ],
+            ..MinuteReport::default()
+        };
+        let res = colorize(Station::Dcf77, &report);
+        assert_eq!(res[0], format!("{RED}increase_second() == false{RESET}\n"));
+        assert!(res[1].starts_with(&format!("\x1b[90m0{RESET} \x1b[34m0{RESET}")));
+        assert!(res[1].ends_with(&format!(
+            " {FAILED_PARITY}0{RESET}{FAILED_PARITY}0{RESET}{FAILED_PARITY}0{RESET}\
+             {FAILED_PARITY}0{RESET}{FAILED_PARITY}0{RESET}{FAILED_PARITY}0{RESET}\
+             {FAILED_PARITY}1{RESET}\n"
+        )));
+        assert_eq!(res[2], "Third-party buffer=0x0000\n");
+        assert_eq!(res[3], format!("{RED}Minute parity bad{RESET}\n"));
+        assert_eq!(res[4], "\n");
+    }
+
+    #[test]
+    fn test_colorize_missing() {
+        let report = MinuteReport {
+            bits: String::from("4_"),
+            lines: vec![String::from("4 _\n"), String::from("\n")],
+            ..MinuteReport::default()
+        };
+        assert_eq!(
+            colorize(Station::Msf, &report)[0],
+            format!("\x1b[90m4{RESET} {MISSING}_{RESET}\n")
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use crate::transmitters::{str_time, MinuteReport};
+use crate::Station;
+use std::collections::BTreeMap;
+
+/// Return the differences between the logs of two receivers of the same station, aligned by
+/// decoded time, followed by the quality score of each receiver, as text.
+///
+/// Only minutes with a completely decoded time can be aligned. They are aligned by UTC, so that
+/// the repeated hour at the end of daylight saving time is not mixed up, or all of them by local
+/// time if the state of daylight saving time is not decoded for any of them. If a receiver decoded
+/// the same time more than once, its first valid minute is used.
+///
+/// # Arguments
+/// * `station` - the station which was received, for its time zone
+/// * `first` - the analysis of each minute of the first receiver
+/// * `second` - the analysis of each minute of the second receiver
+pub fn compare_minutes(
+    station: Station,
+    first: &[MinuteReport],
+    second: &[MinuteReport],
+) -> Vec<String> {
+    // mixing UTC and local time would misalign the receivers by the offset of the station
+    let by_utc = [first, second]
+        .iter()
+        .flat_map(|reports| reports.iter())
+        .filter_map(|report| report.time)
+        .all(|time| time.minutes().is_none() || time.unix_time(station).is_some());
+    let mut aligned: BTreeMap<i64, [Option<&MinuteReport>; 2]> = BTreeMap::new();
+    for (receiver, reports) in [first, second].iter().enumerate() {
+        for report in reports.iter() {
+            let Some(key) = report.time.and_then(|time| {
+                if by_utc {
+                    time.unix_time(station)
+                } else {
+                    time.minutes()
+                }
+            }) else {
+                continue;
+            };
+            let slot = &mut aligned.entry(key).or_default()[receiver];
+            if slot.is_none_or(|s_report| !s_report.is_good() && report.is_good()) {
+                *slot = Some(report);
+            }
+        }
+    }
+    let mut res = Vec::new();
+    let mut only_valid = [0, 0];
+    for pair in aligned.values() {
+        let (receiver, report) = match pair {
+            [Some(s_first), Some(s_second)] => {
+                let time = str_time(&s_first.time.unwrap());
+                let diffs = bit_differences(&s_first.bits, &s_second.bits);
+                if !diffs.is_empty() {
+                    res.push(format!("{time}: bits differ at seconds {diffs}"));
+                }
+                match (s_first.is_good(), s_second.is_good()) {
+                    (true, false) => (0, s_first),
+                    (false, true) => (1, s_second),
+                    _ => continue,
+                }
+            }
+            [Some(s_first), None] => (0, s_first),
+            [None, Some(s_second)] => (1, s_second),
+            [None, None] => continue,
+        };
+        let time = str_time(&report.time.unwrap());
+        if pair[1 - receiver].is_none() {
+            res.push(format!("{time}: only decoded by receiver {}", receiver + 1));
+        }
+        if report.is_good() {
+            res.push(format!("{time}: only valid for receiver {}", receiver + 1));
+            only_valid[receiver] += 1;
+        }
+    }
+    for (receiver, reports) in [first, second].iter().enumerate() {
+        let good = reports.iter().filter(|report| report.is_good()).count();
+        res.push(format!(
+            "Receiver {}: good={good}/{} ({:.1}%) only_valid={}",
+            receiver + 1,
+            reports.len(),
+            if reports.is_empty() {
+                0.0
+            } else {
+                good as f64 * 100.0 / reports.len() as f64
+            },
+            only_valid[receiver]
+        ));
+    }
+    res
+}
+
+/// Return the seconds at which the given minutes differ as a comma-separated list, including
+/// the seconds which are present in only one of them.
+///
+/// # Arguments
+/// * `first` - the bits of the first minute
+/// * `second` - the bits of the second minute
+fn bit_differences(first: &str, second: &str) -> String {
+    let first: Vec<char> = first.chars().collect();
+    let second: Vec<char> = second.chars().collect();
+    (0..first.len().max(second.len()))
+        .filter(|&idx| first.get(idx) != second.get(idx))
+        .map(|idx| idx.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::{DecodedTime, ErrorKind, MinuteError};
+
+    /// Return a report for the given minute after 10:00 with the given bits and errors.
+    fn report(minute: u8, bits: &str, errors: &[(ErrorKind, &str)]) -> MinuteReport {
+        MinuteReport {
+            bits: String::from(bits),
+            time: Some(DecodedTime {
+                year: Some(24),
+                month: Some(5),
+                day: Some(1),
+                hour: Some(10),
+                minute: Some(minute),
+                ..DecodedTime::default()
+            }),
+            errors: errors
+                .iter()
+                .map(|&(kind, message)| MinuteError::new(kind, message))
+                .collect(),
+            ..MinuteReport::default()
+        }
+    }
+
+    #[test]
+    fn test_bit_differences() {
+        assert_eq!(bit_differences("0101", "0101"), "");
+        assert_eq!(bit_differences("0101", "1100_"), "0,3,4");
+    }
+
+    #[test]
+    fn test_compare_minutes() {
+        let first = vec![
+            report(0, "0101", &[]),
+            report(1, "0110", &[(ErrorKind::Parity, "Hour parity bad")]),
+            report(2, "0111", &[]),
+        ];
+        let second = vec![
+            report(1, "0100", &[]),
+            report(2, "0111", &[]),
+            report(3, "1000", &[]),
+            MinuteReport::default(),
+        ];
+        assert_eq!(
+            compare_minutes(Station::Dcf77, &first, &second),
+            vec![
+                "24-05-01 10:00: only decoded by receiver 1",
+                "24-05-01 10:00: only valid for receiver 1",
+                "24-05-01 10:01: bits differ at seconds 2",
+                "24-05-01 10:01: only valid for receiver 2",
+                "24-05-01 10:03: only decoded by receiver 2",
+                "24-05-01 10:03: only valid for receiver 2",
+                "Receiver 1: good=2/3 (66.7%) only_valid=1",
+                "Receiver 2: good=3/4 (75.0%) only_valid=2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_minutes_duplicate() {
+        let first = vec![
+            report(0, "0000", &[(ErrorKind::Jump, "Minute jumped")]),
+            report(0, "0001", &[]),
+        ];
+        let second = vec![report(0, "0001", &[])];
+        assert_eq!(
+            compare_minutes(Station::Dcf77, &first, &second),
+            vec![
+                "Receiver 1: good=1/2 (50.0%) only_valid=0",
+                "Receiver 2: good=1/1 (100.0%) only_valid=0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_minutes_dst() {
+        // 02:30 occurs twice on 2024-10-27, once in summer time and once in winter time
+        let with_dst = |dst: u8| {
+            let mut res = report(30, "0001", &[]);
+            res.time = Some(DecodedTime {
+                month: Some(10),
+                day: Some(27),
+                hour: Some(2),
+                dst: Some(dst),
+                ..res.time.unwrap()
+            });
+            res
+        };
+        let first = vec![with_dst(radio_datetime_utils::DST_SUMMER), with_dst(0)];
+        let second = vec![with_dst(0)];
+        assert_eq!(
+            compare_minutes(Station::Dcf77, &first, &second),
+            vec![
+                "24-10-27 02:30: only decoded by receiver 1",
+                "24-10-27 02:30: only valid for receiver 1",
+                "Receiver 1: good=2/2 (100.0%) only_valid=1",
+                "Receiver 2: good=1/1 (100.0%) only_valid=0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_minutes_dst_unknown() {
+        // the second receiver does not decode the state of daylight saving time
+        let with_dst = |minute: u8| {
+            let mut res = report(minute, "0001", &[]);
+            res.time = Some(DecodedTime {
+                dst: Some(0),
+                ..res.time.unwrap()
+            });
+            res
+        };
+        let first = vec![with_dst(0), with_dst(1)];
+        let second = vec![report(0, "0001", &[]), report(1, "0011", &[])];
+        assert_eq!(
+            compare_minutes(Station::Dcf77, &first, &second),
+            vec![
+                "24-05-01 10:01: bits differ at seconds 2",
+                "Receiver 1: good=2/2 (100.0%) only_valid=0",
+                "Receiver 2: good=2/2 (100.0%) only_valid=0",
+            ]
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::env;
+use std::path::PathBuf;
+use toml::{Table, Value};
+
+/// How the value of a key in the configuration file is passed to its command line option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    /// a boolean, the option is given without value if true
+    Flag,
+    /// a string or number, the option is given once with it as value
+    Single,
+    /// an array, the option is given once with the elements joined by commas
+    List,
+    /// an array, the option is given once per element
+    Repeated,
+    /// a table of numbers, the option is given once per `key=value`
+    Map,
+}
+
+/// The keys of the configuration file with their table (empty for the top level), kind and the
+/// command line option they set.
+const KEYS: &[(&str, &str, Kind, &str)] = &[
+    ("", "leap_seconds", Kind::Single, "--leap-seconds"),
+    ("", "century_pivot", Kind::Single, "--century-pivot"),
+    ("input", "format", Kind::Single, "--input"),
+    ("input", "invert", Kind::Flag, "--invert"),
+    ("input", "baud", Kind::Single, "--baud"),
+    (
+        "input",
+        "timestamp_patterns",
+        Kind::Repeated,
+        "--timestamp-pattern",
+    ),
+    ("pulses", "widths", Kind::Map, "--pulse-width"),
+    ("pulses", "tolerance", Kind::Single, "--pulse-tolerance"),
+    ("pulses", "minute_gap", Kind::Single, "--minute-gap"),
+    ("output", "format", Kind::Single, "--format"),
+    ("output", "color", Kind::Single, "--color"),
+    ("output", "grouping", Kind::Single, "--grouping"),
+    ("output", "timeline", Kind::Flag, "--timeline"),
+    ("filter", "only_errors", Kind::Flag, "--only-errors"),
+    ("filter", "only", Kind::List, "--only"),
+    ("filter", "from", Kind::Single, "--from"),
+    ("filter", "to", Kind::Single, "--to"),
+    ("sinks", "listen", Kind::Single, "--listen"),
+    ("sinks", "textfile", Kind::Single, "--textfile"),
+    ("sinks", "shm", Kind::Single, "--shm"),
+    ("sinks", "sock", Kind::Single, "--sock"),
+    ("sinks", "events", Kind::Single, "--events"),
+    (
+        "sinks",
+        "parity_threshold",
+        Kind::Single,
+        "--parity-threshold",
+    ),
+    ("sinks", "gap_threshold", Kind::Single, "--gap-threshold"),
+    ("sinks", "database", Kind::Single, "--database"),
+];
+
+/// The settings of a configuration file, as command line options.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// the default station, used if none is given on the command line
+    pub station: Option<String>,
+    /// the command line options equivalent to the other settings
+    pub args: Vec<String>,
+}
+
+impl Config {
+    /// Parse the given TOML configuration file into the equivalent command line options, or
+    /// return an error for invalid syntax or an unknown key or type of value. The values
+    /// themselves are checked when the options are parsed. The options of a file are in the
+    /// order of their keys, which does not matter as every option occurs in one key only.
+    ///
+    /// # Arguments
+    /// * `text` - the contents of the configuration file
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let table = text.parse::<Table>().map_err(|error| error.to_string())?;
+        let mut config = Self::default();
+        for (key, value) in &table {
+            match value {
+                Value::String(s_station) if key == "station" => {
+                    config.station = Some(s_station.clone())
+                }
+                _ if key == "station" => return Err(String::from("'station' must be a string")),
+                Value::Table(s_table) if KEYS.iter().any(|k| k.0 == key) => {
+                    for (s_key, s_value) in s_table {
+                        config.add(key, s_key, s_value)?;
+                    }
+                }
+                _ => config.add("", key, value)?,
+            }
+        }
+        Ok(config)
+    }
+
+    /// Add the command line option for the given key and value.
+    ///
+    /// # Arguments
+    /// * `table` - the table of the key, empty for the top level
+    /// * `key` - the key within the table
+    /// * `value` - the value of the key
+    fn add(&mut self, table: &str, key: &str, value: &Value) -> Result<(), String> {
+        let name = if table.is_empty() {
+            String::from(key)
+        } else {
+            format!("{table}.{key}")
+        };
+        let Some(&(_, _, kind, option)) = KEYS.iter().find(|k| k.0 == table && k.1 == key) else {
+            return Err(format!("Unknown key '{name}'"));
+        };
+        let option = String::from(option);
+        match (kind, value) {
+            (Kind::Flag, Value::Boolean(s_flag)) => {
+                if *s_flag {
+                    self.args.push(option);
+                }
+            }
+            (Kind::Single, _) => {
+                let value = scalar(value).ok_or(format!("'{name}' must be a string or number"))?;
+                self.args.extend([option, value]);
+            }
+            (Kind::List | Kind::Repeated, Value::Array(s_values)) => {
+                let values = s_values
+                    .iter()
+                    .map(scalar)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(format!("'{name}' must be an array of strings"))?;
+                if kind == Kind::List {
+                    self.args.extend([option, values.join(",")]);
+                } else {
+                    for value in values {
+                        self.args.extend([option.clone(), value]);
+                    }
+                }
+            }
+            (Kind::Map, Value::Table(s_table)) => {
+                for (s_key, s_value) in s_table {
+                    let Value::Integer(s_number) = s_value else {
+                        return Err(format!("'{name}.{s_key}' must be a number"));
+                    };
+                    self.args
+                        .extend([option.clone(), format!("{s_key}={s_number}")]);
+                }
+            }
+            (Kind::Flag, _) => return Err(format!("'{name}' must be true or false")),
+            (Kind::List | Kind::Repeated, _) => {
+                return Err(format!("'{name}' must be an array of strings"))
+            }
+            (Kind::Map, _) => return Err(format!("'{name}' must be a table of numbers")),
+        }
+        Ok(())
+    }
+}
+
+/// Remove the options which are given again in the given later options from the options of the
+/// configuration files, so that the later ones replace them. Most options take the last value
+/// anyway, but repeated options would add to the values of the files and flags could not be
+/// turned off, which is done with `--no-` before the name of the flag.
+///
+/// # Arguments
+/// * `args` - the options of the configuration files read so far, see `Config::args`
+/// * `later_args` - the options of a later configuration file or of the command line
+pub fn remove_overridden(args: &mut Vec<String>, later_args: &[String]) {
+    let given: Vec<String> = later_args
+        .iter()
+        .filter_map(|arg| arg.split('=').next()?.strip_prefix("--"))
+        .map(|name| format!("--{}", name.strip_prefix("no-").unwrap_or(name)))
+        .collect();
+    let mut remaining = std::mem::take(args).into_iter();
+    while let Some(option) = remaining.next() {
+        let is_flag = KEYS
+            .iter()
+            .any(|&(_, _, kind, k_option)| k_option == option && kind == Kind::Flag);
+        let value = if is_flag { None } else { remaining.next() };
+        if !given.contains(&option) {
+            args.push(option);
+            args.extend(value);
+        }
+    }
+}
+
+/// Return the given string or number as string, or None for other values.
+///
+/// # Arguments
+/// * `value` - the value to convert
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s_string) => Some(s_string.clone()),
+        Value::Integer(s_number) => Some(s_number.to_string()),
+        Value::Float(s_number) => Some(s_number.to_string()),
+        _ => None,
+    }
+}
+
+/// Return the system-wide and the per-user configuration file, in the order in which they are
+/// read. Later files override earlier ones. The files do not need to exist.
+pub fn default_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from("/etc/radio_datetime_analyzer.toml")];
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    if let Some(s_config_home) = config_home {
+        paths.push(s_config_home.join("radio_datetime_analyzer/config.toml"));
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            "station = \"msf\"\n\
+             century_pivot = 90\n\
+             [input]\n\
+             format = \"timestamped\"\n\
+             invert = false\n\
+             timestamp_patterns = [\"%d.%m.%Y %H:%M:%S\", \"%H:%M:%S\"]\n\
+             [pulses]\n\
+             widths = { \"0\" = 100 }\n\
+             tolerance = 40\n\
+             [output]\n\
+             timeline = true\n\
+             [filter]\n\
+             only = [\"parity\", \"jump\"]\n",
+        );
+        assert_eq!(
+            config,
+            Ok(Config {
+                station: Some(String::from("msf")),
+                args: [
+                    "--century-pivot",
+                    "90",
+                    "--only",
+                    "parity,jump",
+                    "--input",
+                    "timestamped",
+                    "--timestamp-pattern",
+                    "%d.%m.%Y %H:%M:%S",
+                    "--timestamp-pattern",
+                    "%H:%M:%S",
+                    "--timeline",
+                    "--pulse-tolerance",
+                    "40",
+                    "--pulse-width",
+                    "0=100",
+                ]
+                .map(String::from)
+                .to_vec(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_remove_overridden() {
+        let mut args = [
+            "--timestamp-pattern",
+            "%H:%M:%S",
+            "--timeline",
+            "--pulse-width",
+            "0=100",
+            "--pulse-width",
+            "1=200",
+            "--input",
+            "pulses",
+        ]
+        .map(String::from)
+        .to_vec();
+        remove_overridden(
+            &mut args,
+            &[
+                "--pulse-width=0=90",
+                "--no-timeline",
+                "--timestamp-pattern",
+                "%S",
+                "msf",
+            ]
+            .map(String::from),
+        );
+        assert_eq!(args, ["--input", "pulses"]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Config::parse("station =").is_err());
+        assert_eq!(
+            Config::parse("[output]\nfont = \"mono\""),
+            Err(String::from("Unknown key 'output.font'"))
+        );
+        assert_eq!(
+            Config::parse("[colour]\nfont = \"mono\""),
+            Err(String::from("Unknown key 'colour'"))
+        );
+        assert_eq!(
+            Config::parse("[output]\ntimeline = 1"),
+            Err(String::from("'output.timeline' must be true or false"))
+        );
+        assert_eq!(
+            Config::parse("[filter]\nonly = \"parity\""),
+            Err(String::from("'filter.only' must be an array of strings"))
+        );
+        assert_eq!(
+            Config::parse("[pulses]\nwidths = { \"0\" = \"100\" }"),
+            Err(String::from("'pulses.widths.0' must be a number"))
+        );
+        assert_eq!(
+            Config::parse("station = 1"),
+            Err(String::from("'station' must be a string"))
+        );
+    }
+}
@@ -0,0 +1,444 @@
+use crate::filter::{MinuteFilter, MinuteKind};
+use crate::transmitters::{str_time, DecodedTime, MinuteReport};
+use crate::Station;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::fs;
+use std::time::UNIX_EPOCH;
+
+/// The tables and indexes of the database, created if they do not exist yet.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS files (
+        id INTEGER PRIMARY KEY,
+        path TEXT NOT NULL UNIQUE,
+        station TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        modified INTEGER NOT NULL,
+        imported INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS minutes (
+        id INTEGER PRIMARY KEY,
+        file_id INTEGER NOT NULL REFERENCES files (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        station TEXT NOT NULL,
+        bits TEXT NOT NULL,
+        year INTEGER,
+        month INTEGER,
+        day INTEGER,
+        weekday INTEGER,
+        hour INTEGER,
+        minute INTEGER,
+        dst INTEGER,
+        leap_second INTEGER,
+        dut1 INTEGER,
+        time INTEGER,
+        utc INTEGER,
+        good INTEGER NOT NULL,
+        details TEXT NOT NULL,
+        UNIQUE (file_id, position)
+    );
+    CREATE INDEX IF NOT EXISTS minutes_time ON minutes (station, time);
+    CREATE INDEX IF NOT EXISTS minutes_date ON minutes (station, year, month, day, hour);
+    CREATE INDEX IF NOT EXISTS minutes_utc ON minutes (utc);
+    CREATE TABLE IF NOT EXISTS errors (
+        minute_id INTEGER NOT NULL REFERENCES minutes (id) ON DELETE CASCADE,
+        kind TEXT NOT NULL,
+        message TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS errors_minute ON errors (minute_id);
+    CREATE INDEX IF NOT EXISTS errors_kind ON errors (kind, message);
+";
+
+/// A SQLite database with the analysis of each minute of the imported log files.
+///
+/// Each log file has a row in `files`, each of its minutes a row in `minutes` with the bits,
+/// the decoded date and time in the local time of the station (`time` in minutes since
+/// 2000-01-01 00:00, `utc` in seconds since 1970-01-01 00:00 UTC) and each problem of a minute a
+/// row in `errors` with its kind as written by `transmitters::ErrorKind`.
+pub struct Database {
+    connection: Connection,
+}
+
+impl Database {
+    /// Open the given database, creating it and its tables if needed.
+    ///
+    /// # Arguments
+    /// * `path` - the file name of the database, `:memory:` for a temporary one
+    pub fn open(path: &str) -> Result<Self, String> {
+        let connection = Connection::open(path)
+            .map_err(|error| format!("Could not open database '{path}' : {error}"))?;
+        connection
+            .execute_batch(&format!("PRAGMA foreign_keys = ON;{SCHEMA}"))
+            .map_err(|error| format!("Could not create tables in database '{path}' : {error}"))?;
+        Ok(Self { connection })
+    }
+
+    /// Return if the given log file is imported for the given station and did not change since.
+    ///
+    /// # Arguments
+    /// * `station` - the station which was received
+    /// * `path` - the name of the log file
+    pub fn is_imported(&self, station: Station, path: &str) -> Result<bool, String> {
+        let (path, size, modified) = identify(path)?;
+        let imported = self
+            .connection
+            .query_row(
+                "SELECT size, modified FROM files WHERE path = ?1 AND station = ?2",
+                params![path, station.to_string()],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+            )
+            .optional()
+            .map_err(sql_error)?;
+        Ok(imported == Some((size, modified)))
+    }
+
+    /// Store the analysis of the given log file, replacing that of an earlier import of it, also
+    /// if that was for the other station.
+    ///
+    /// # Arguments
+    /// * `station` - the station which was received
+    /// * `path` - the name of the log file
+    /// * `reports` - the analysis of all minutes of the log file
+    pub fn import(
+        &mut self,
+        station: Station,
+        path: &str,
+        reports: &[MinuteReport],
+    ) -> Result<(), String> {
+        let (path, size, modified) = identify(path)?;
+        let transaction = self.connection.transaction().map_err(sql_error)?;
+        transaction
+            .execute("DELETE FROM files WHERE path = ?1", params![path])
+            .map_err(sql_error)?;
+        transaction
+            .execute(
+                "INSERT INTO files (path, station, size, modified, imported) \
+                 VALUES (?1, ?2, ?3, ?4, unixepoch())",
+                params![path, station.to_string(), size, modified],
+            )
+            .map_err(sql_error)?;
+        let file_id = transaction.last_insert_rowid();
+        {
+            let mut insert_minute = transaction
+                .prepare(
+                    "INSERT INTO minutes (file_id, position, station, bits, year, month, day, \
+                     weekday, hour, minute, dst, leap_second, dut1, time, utc, good, details) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, \
+                     ?16, ?17)",
+                )
+                .map_err(sql_error)?;
+            let mut insert_error = transaction
+                .prepare("INSERT INTO errors (minute_id, kind, message) VALUES (?1, ?2, ?3)")
+                .map_err(sql_error)?;
+            for report in reports {
+                let time = report.time.unwrap_or_default();
+                insert_minute
+                    .execute(params![
+                        file_id,
+                        report.index as i64,
+                        station.to_string(),
+                        report.bits,
+                        time.year,
+                        time.month,
+                        time.day,
+                        time.weekday,
+                        time.hour,
+                        time.minute,
+                        time.dst,
+                        time.leap_second,
+                        report.dut1,
+                        time.minutes(),
+                        time.unix_time(station),
+                        report.is_good(),
+                        report.details().join("\n"),
+                    ])
+                    .map_err(sql_error)?;
+                let minute_id = transaction.last_insert_rowid();
+                for error in &report.errors {
+                    insert_error
+                        .execute(params![minute_id, error.kind.to_string(), error.message])
+                        .map_err(sql_error)?;
+                }
+            }
+        }
+        transaction.commit().map_err(sql_error)
+    }
+
+    /// Return the stored minutes of the given station which match the given filter, as
+    /// `file minute n: YY-MM-DD HH:MM problems` in the order of the files and their minutes.
+    ///
+    /// # Arguments
+    /// * `station` - the station to return the minutes of
+    /// * `filter` - the selection of the minutes
+    /// * `message` - if not None, only return minutes with a problem containing this text
+    pub fn query(
+        &self,
+        station: Station,
+        filter: &MinuteFilter,
+        message: Option<&str>,
+    ) -> Result<Vec<String>, String> {
+        let mut conditions = vec![String::from("m.station = ?")];
+        let mut values = vec![Value::Text(station.to_string())];
+        if filter.only_errors {
+            conditions.push(String::from(
+                "EXISTS (SELECT 1 FROM errors e WHERE e.minute_id = m.id)",
+            ));
+        }
+        if !filter.only.is_empty() {
+            let mut any = Vec::new();
+            let kinds: Vec<_> = filter
+                .only
+                .iter()
+                .filter_map(|kind| match kind {
+                    MinuteKind::Error(s_kind) => Some(s_kind),
+                    MinuteKind::Dst => None,
+                })
+                .collect();
+            if !kinds.is_empty() {
+                any.push(format!(
+                    "EXISTS (SELECT 1 FROM errors e WHERE e.minute_id = m.id AND e.kind IN ({}))",
+                    vec!["?"; kinds.len()].join(", ")
+                ));
+                values.extend(kinds.into_iter().map(|kind| Value::Text(kind.to_string())));
+            }
+            if filter.only.contains(&MinuteKind::Dst) {
+                any.push(format!(
+                    "m.dst & {} != 0",
+                    radio_datetime_utils::DST_ANNOUNCED
+                        | radio_datetime_utils::DST_PROCESSED
+                        | radio_datetime_utils::DST_JUMP
+                ));
+            }
+            conditions.push(format!("({})", any.join(" OR ")));
+        }
+        if let Some(s_from) = filter.from {
+            conditions.push(String::from("m.time >= ?"));
+            values.push(Value::Integer(s_from));
+        }
+        if let Some(s_to) = filter.to {
+            conditions.push(String::from("m.time <= ?"));
+            values.push(Value::Integer(s_to));
+        }
+        if let Some(s_message) = message {
+            conditions.push(String::from(
+                "EXISTS (SELECT 1 FROM errors e WHERE e.minute_id = m.id AND e.message LIKE ?)",
+            ));
+            values.push(Value::Text(format!("%{s_message}%")));
+        }
+        let mut statement = self
+            .connection
+            .prepare(&format!(
+                "SELECT f.path, m.position, m.year, m.month, m.day, m.hour, m.minute, \
+                 (SELECT group_concat(e.message, ', ') FROM errors e WHERE e.minute_id = m.id) \
+                 FROM minutes m JOIN files f ON f.id = m.file_id WHERE {} \
+                 ORDER BY f.path, m.position",
+                conditions.join(" AND ")
+            ))
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map(params_from_iter(values), |row| {
+                let time = DecodedTime {
+                    year: row.get(2)?,
+                    month: row.get(3)?,
+                    day: row.get(4)?,
+                    hour: row.get(5)?,
+                    minute: row.get(6)?,
+                    ..DecodedTime::default()
+                };
+                let errors: Option<String> = row.get(7)?;
+                Ok(format!(
+                    "{} minute {}: {} {}",
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)? + 1,
+                    str_time(&time),
+                    errors.unwrap_or_default()
+                )
+                .trim_end()
+                .to_string())
+            })
+            .map_err(sql_error)?;
+        rows.collect::<Result<_, _>>().map_err(sql_error)
+    }
+
+    /// Run the given SQL query and return its column names and rows, with the values separated
+    /// by tabs.
+    ///
+    /// # Arguments
+    /// * `sql` - the query to run, e.g. `SELECT kind, count(*) FROM errors GROUP BY kind`
+    pub fn query_sql(&self, sql: &str) -> Result<Vec<String>, String> {
+        let mut statement = self.connection.prepare(sql).map_err(sql_error)?;
+        let columns = statement.column_count();
+        let mut lines = vec![statement.column_names().join("\t")];
+        let mut rows = statement.query([]).map_err(sql_error)?;
+        while let Some(row) = rows.next().map_err(sql_error)? {
+            let mut values = Vec::new();
+            for idx in 0..columns {
+                values.push(match row.get_ref(idx).map_err(sql_error)? {
+                    ValueRef::Null => String::from(""),
+                    ValueRef::Integer(s_value) => s_value.to_string(),
+                    ValueRef::Real(s_value) => s_value.to_string(),
+                    ValueRef::Text(s_value) => String::from_utf8_lossy(s_value).into_owned(),
+                    ValueRef::Blob(s_value) => format!("<{} bytes>", s_value.len()),
+                });
+            }
+            lines.push(values.join("\t"));
+        }
+        Ok(lines)
+    }
+}
+
+/// Return the canonical path, size and modification time in seconds of the given log file,
+/// which identify its contents in the database.
+///
+/// # Arguments
+/// * `path` - the name of the log file
+fn identify(path: &str) -> Result<(String, i64, i64), String> {
+    let canonical = fs::canonicalize(path)
+        .map_err(|error| format!("Could not read file '{path}' : {error}"))?;
+    let metadata = fs::metadata(&canonical)
+        .map_err(|error| format!("Could not read file '{path}' : {error}"))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |time| time.as_secs() as i64);
+    Ok((
+        canonical.to_string_lossy().into_owned(),
+        metadata.len() as i64,
+        modified,
+    ))
+}
+
+/// Return the given SQLite error in plain English.
+///
+/// # Arguments
+/// * `error` - the error to describe
+fn sql_error(error: rusqlite::Error) -> String {
+    format!("Database error : {error}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::{ErrorKind, MinuteError};
+
+    /// Return a report for the given minute of 2024-03-DD 01:MM with the given problems.
+    fn report(index: usize, day: u8, minute: u8, errors: &[(ErrorKind, &str)]) -> MinuteReport {
+        MinuteReport {
+            index,
+            bits: String::from("0101"),
+            time: Some(DecodedTime {
+                year: Some(24),
+                month: Some(3),
+                day: Some(day),
+                weekday: Some(7),
+                hour: Some(1),
+                minute: Some(minute),
+                dst: Some(0),
+                leap_second: Some(0),
+                ..DecodedTime::default()
+            }),
+            errors: errors
+                .iter()
+                .map(|&(kind, message)| MinuteError::new(kind, message))
+                .collect(),
+            ..MinuteReport::default()
+        }
+    }
+
+    /// Return the name of a new temporary log file with the given contents.
+    fn log_file(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rda-{}-{name}", std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_import() {
+        let path = log_file("import.log", "0101\n");
+        let mut database = Database::open(":memory:").unwrap();
+        assert_eq!(database.is_imported(Station::Dcf77, &path), Ok(false));
+        let reports = [
+            report(0, 30, 58, &[]),
+            report(1, 30, 59, &[(ErrorKind::Parity, "Hour parity bad")]),
+        ];
+        database.import(Station::Dcf77, &path, &reports).unwrap();
+        assert_eq!(database.is_imported(Station::Dcf77, &path), Ok(true));
+        assert_eq!(database.is_imported(Station::Msf, &path), Ok(false));
+        // importing again replaces the earlier rows
+        database.import(Station::Dcf77, &path, &reports).unwrap();
+        assert_eq!(
+            database.query_sql("SELECT count(*), sum(good), min(utc) FROM minutes"),
+            Ok(vec![
+                String::from("count(*)\tsum(good)\tmin(utc)"),
+                String::from("2\t1\t1711760280")
+            ])
+        );
+        assert_eq!(
+            database.query_sql("SELECT kind, message FROM errors"),
+            Ok(vec![
+                String::from("kind\tmessage"),
+                String::from("parity\tHour parity bad")
+            ])
+        );
+        fs::write(&path, "0101\n0110\n").unwrap();
+        assert_eq!(database.is_imported(Station::Dcf77, &path), Ok(false));
+        fs::remove_file(&path).unwrap();
+        assert!(database
+            .is_imported(Station::Dcf77, &path)
+            .unwrap_err()
+            .starts_with("Could not read file"));
+    }
+
+    #[test]
+    fn test_query() {
+        let path = log_file("query.log", "0101\n");
+        let mut database = Database::open(":memory:").unwrap();
+        database
+            .import(
+                Station::Dcf77,
+                &path,
+                &[
+                    report(0, 29, 10, &[(ErrorKind::Parity, "Minute parity bad")]),
+                    report(1, 30, 10, &[(ErrorKind::Parity, "Hour parity bad")]),
+                    report(
+                        2,
+                        31,
+                        10,
+                        &[
+                            (ErrorKind::Parity, "Hour parity undetermined"),
+                            (ErrorKind::Jump, "Minute jumped"),
+                        ],
+                    ),
+                ],
+            )
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+        let filter = MinuteFilter {
+            only: vec![MinuteKind::Error(ErrorKind::Parity)],
+            from: Some(crate::filter::parse_time("2024-03-30 00:00").unwrap()),
+            ..MinuteFilter::default()
+        };
+        assert_eq!(
+            database.query(Station::Dcf77, &filter, Some("hour parity")),
+            Ok(vec![
+                format!("{path} minute 2: 24-03-30 01:10 Hour parity bad"),
+                format!("{path} minute 3: 24-03-31 01:10 Hour parity undetermined, Minute jumped")
+            ])
+        );
+        assert_eq!(
+            database.query(Station::Dcf77, &MinuteFilter::default(), Some("jumped")),
+            Ok(vec![format!(
+                "{path} minute 3: 24-03-31 01:10 Hour parity undetermined, Minute jumped"
+            )])
+        );
+        assert_eq!(
+            database.query(Station::Msf, &MinuteFilter::default(), None),
+            Ok(Vec::new())
+        );
+        assert!(database
+            .query_sql("SELECT * FROM nothing")
+            .unwrap_err()
+            .starts_with("Database error"));
+    }
+}
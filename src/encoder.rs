@@ -0,0 +1,337 @@
+use crate::leap_seconds::LeapSecondTable;
+use crate::{civil_from_days, days_from_civil, transmitters, Station};
+
+/// Everything which is broadcast about one minute, in the local time of the station.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MinuteInfo {
+    /// year within the century, 0-99
+    pub year: u8,
+    pub month: u8,
+    pub day: u8,
+    /// day of the week, 0 is Sunday
+    pub weekday: u8,
+    pub hour: u8,
+    pub minute: u8,
+    /// if daylight saving time is in effect
+    pub summer: bool,
+    /// if a change of daylight saving time is announced
+    pub dst_announced: bool,
+    /// if a leap second is announced
+    pub leap_announced: bool,
+    /// the leap second which is inserted (1) or removed (-1) in this minute, 0 for none
+    pub leap_second: i8,
+    /// difference between UT1 and UTC in units of 0.1 seconds, -8..=8
+    pub dut1: i8,
+    pub call_bit: bool,
+    /// 14-bit third-party payload
+    pub third_party: u16,
+}
+
+/// Settings for the encoder which do not follow from the date and time.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EncoderSettings {
+    /// difference between UT1 and UTC in units of 0.1 seconds, -8..=8, only used by MSF
+    pub dut1: i8,
+    /// value of the call bit, only used by DCF77
+    pub call_bit: bool,
+    /// third-party payloads, used in turn for each minute, only used by DCF77
+    pub third_party: Vec<u16>,
+}
+
+/// Return a log with one line of bits per minute for the given station, as consumed by
+/// `dcf77::analyze_buffer()` and `msf::analyze_buffer()`.
+///
+/// Both stations broadcast the time of the minute starting at the next minute marker, so the
+/// first line contains the minute after `start`. Daylight saving time follows the EU and UK
+/// rules, which switch at the same moment.
+///
+/// # Arguments
+/// * `station` - the station to encode for
+/// * `start` - UTC time in seconds since 1970-01-01 of the minute in which the first line is
+///   broadcast, seconds are ignored
+/// * `minutes` - the number of minutes to encode
+/// * `settings` - values which do not follow from the date and time
+/// * `leap_seconds` - table of scheduled leap seconds
+pub fn encode_log(
+    station: Station,
+    start: i64,
+    minutes: u32,
+    settings: &EncoderSettings,
+    leap_seconds: &LeapSecondTable,
+) -> String {
+    let mut res = String::from("");
+    let (utc_offset, dst_warning) = match station {
+        Station::Dcf77 => (60, 60),
+        Station::Msf => (0, 61),
+    };
+    for idx in 0..minutes as i64 {
+        // UTC minute in which this minute is broadcast, and the UTC minute which it encodes
+        let broadcast = start.div_euclid(60) + idx;
+        let encoded = broadcast + 1;
+        let summer = is_summer(encoded);
+        let local = encoded + utc_offset + if summer { 60 } else { 0 };
+        let days = local.div_euclid(1440);
+        let (year, month, day) = civil_from_days(days);
+        let (leap_announced, leap_second) = leap_second_info(broadcast, leap_seconds);
+        let info = MinuteInfo {
+            year: (year % 100) as u8,
+            month,
+            day,
+            weekday: (days + 4).rem_euclid(7) as u8,
+            hour: (local.rem_euclid(1440) / 60) as u8,
+            minute: local.rem_euclid(60) as u8,
+            summer,
+            dst_announced: dst_change_within(broadcast, dst_warning),
+            leap_announced,
+            leap_second,
+            dut1: settings.dut1,
+            call_bit: settings.call_bit,
+            third_party: if settings.third_party.is_empty() {
+                0
+            } else {
+                settings.third_party[idx as usize % settings.third_party.len()]
+            },
+        };
+        res += &match station {
+            Station::Dcf77 => transmitters::dcf77::encode_minute(&info),
+            Station::Msf => transmitters::msf::encode_minute(&info),
+        };
+    }
+    res
+}
+
+/// Return the UTC minutes since 1970-01-01 at which daylight saving time starts and ends in
+/// the given year, which is 01:00 UTC on the last Sunday of March and October.
+///
+/// # Arguments
+/// * `year` - four-digit year
+fn dst_changes(year: u16) -> [i64; 2] {
+    [3, 10].map(|month| {
+        let last_day = days_from_civil(year, month, 31);
+        let last_sunday = last_day - (last_day + 4).rem_euclid(7);
+        last_sunday * 1440 + 60
+    })
+}
+
+/// Return if daylight saving time is in effect at the given UTC minute.
+///
+/// # Arguments
+/// * `minutes` - UTC minutes since 1970-01-01
+fn is_summer(minutes: i64) -> bool {
+    let [start, end] = dst_changes(civil_from_days(minutes.div_euclid(1440)).0);
+    minutes >= start && minutes < end
+}
+
+/// Return if a change of daylight saving time occurs within the given amount of minutes after
+/// the given UTC minute.
+///
+/// # Arguments
+/// * `minutes` - UTC minutes since 1970-01-01
+/// * `window` - the amount of minutes to look ahead
+fn dst_change_within(minutes: i64, window: i64) -> bool {
+    dst_changes(civil_from_days(minutes.div_euclid(1440)).0)
+        .iter()
+        .any(|&change| minutes >= change - window && minutes < change)
+}
+
+/// Return if a leap second is announced in the given UTC minute, and the value of the leap
+/// second which occurs at the end of it. Leap seconds are announced during the last hour.
+///
+/// # Arguments
+/// * `minutes` - UTC minutes since 1970-01-01
+/// * `leap_seconds` - table of scheduled leap seconds
+fn leap_second_info(minutes: i64, leap_seconds: &LeapSecondTable) -> (bool, i8) {
+    let (year, month, _) = civil_from_days(minutes.div_euclid(1440));
+    let Some(value) = leap_seconds.get(year, month) else {
+        return (false, 0);
+    };
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    let leap = days_from_civil(next_year, next_month, 1) * 1440;
+    match leap - minutes {
+        1 => (true, value),
+        2..=60 => (true, 0),
+        _ => (false, 0),
+    }
+}
+
+/// Return the bits of the given BCD value for the given weights, in the order of the weights.
+///
+/// # Arguments
+/// * `value` - the value to encode
+/// * `weights` - the weight of each bit
+pub(crate) fn bcd(value: u8, weights: &[u8]) -> Vec<bool> {
+    let mut sorted = weights.to_vec();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    let mut remaining = value;
+    let mut set = Vec::new();
+    for weight in sorted {
+        if remaining >= weight {
+            remaining -= weight;
+            set.push(weight);
+        }
+    }
+    weights.iter().map(|weight| set.contains(weight)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::AnalysisSettings;
+
+    #[test]
+    fn test_bcd() {
+        assert_eq!(
+            bcd(59, &[1, 2, 4, 8, 10, 20, 40]),
+            vec![true, false, false, true, true, false, true]
+        );
+        assert_eq!(
+            bcd(23, &[20, 10, 8, 4, 2, 1]),
+            vec![true, false, false, false, true, true]
+        );
+    }
+
+    #[test]
+    fn test_dst_changes() {
+        // 2011-03-27 01:00 and 2011-10-30 01:00 UTC
+        assert_eq!(dst_changes(2011), [21_686_460, 21_998_940]);
+        assert!(!is_summer(21_686_459));
+        assert!(is_summer(21_686_460));
+        assert!(is_summer(21_998_939));
+        assert!(!is_summer(21_998_940));
+        assert!(dst_change_within(21_686_400, 60));
+        assert!(!dst_change_within(21_686_399, 60));
+        assert!(dst_change_within(21_686_399, 61));
+    }
+
+    #[test]
+    fn test_leap_second_info() {
+        let table = LeapSecondTable::default();
+        // 2012-06-30 23:59 UTC
+        assert_eq!(leap_second_info(22_351_679, &table), (true, 1));
+        assert_eq!(leap_second_info(22_351_620, &table), (true, 0));
+        assert_eq!(leap_second_info(22_351_619, &table), (false, 0));
+        assert_eq!(leap_second_info(22_351_680, &table), (false, 0));
+    }
+
+    #[test]
+    fn test_encode_log_dcf77() {
+        // 1999-12-31 22:57 UTC
+        assert_eq!(
+            encode_log(
+                Station::Dcf77,
+                946_681_020,
+                2,
+                &EncoderSettings::default(),
+                &LeapSecondTable::default()
+            ),
+            "00000000000000000010100011011110001110001110101001100110011\n\
+             00000000000000000010110011010110001110001110101001100110011\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_log_dcf77_dst() {
+        // 2011-03-27 00:59 UTC, third-party data taken from the sample log
+        assert_eq!(
+            encode_log(
+                Station::Dcf77,
+                1_301_187_540,
+                1,
+                &EncoderSettings {
+                    third_party: vec![4838],
+                    ..EncoderSettings::default()
+                },
+                &LeapSecondTable::default()
+            ),
+            "00110011101001001100100000000110000011100111111000100010001\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_log_dcf77_leap_second() {
+        // 2012-06-30 23:59 UTC
+        assert_eq!(
+            encode_log(
+                Station::Dcf77,
+                1_341_100_740,
+                1,
+                &EncoderSettings {
+                    third_party: vec![12248],
+                    ..EncoderSettings::default()
+                },
+                &LeapSecondTable::default()
+            ),
+            "000011011111101001011000000000100001100000111111000100100010\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_log_msf() {
+        // 2020-03-28 23:58 UTC
+        assert_eq!(
+            encode_log(
+                Station::Msf,
+                1_585_439_880,
+                2,
+                &EncoderSettings {
+                    dut1: -2,
+                    ..EncoderSettings::default()
+                },
+                &LeapSecondTable::default()
+            ),
+            "400000000220000000010000000011101000110100011101100101133110\n\
+             400000000220000000010000000011101001000000000000000003113310\n"
+        );
+        // 2020-03-29 00:59 UTC
+        assert_eq!(
+            encode_log(
+                Station::Msf,
+                1_585_443_540,
+                2,
+                &EncoderSettings {
+                    dut1: -2,
+                    ..EncoderSettings::default()
+                },
+                &LeapSecondTable::default()
+            ),
+            "400000000220000000010000000011101001000000010000000003113130\n\
+             400000000220000000010000000011101001000000010000000101113330\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let table = LeapSecondTable::default();
+        let settings = EncoderSettings {
+            dut1: 3,
+            call_bit: true,
+            third_party: vec![0x1234, 0x2345],
+        };
+        // 2011-03-27 00:30 UTC with a DST change, 2012-06-30 23:30 UTC with a leap second
+        for start in [1_301_185_800, 1_341_099_000] {
+            for res in [
+                transmitters::dcf77::analyze_buffer(
+                    &encode_log(Station::Dcf77, start, 60, &settings, &table),
+                    &AnalysisSettings::default(),
+                ),
+                transmitters::msf::analyze_buffer(
+                    &encode_log(Station::Msf, start, 60, &settings, &table),
+                    &AnalysisSettings::default(),
+                ),
+            ] {
+                for line in res {
+                    assert!(
+                        !["bad", "jumped", "wrong", "Minute is", "eap second"]
+                            .iter()
+                            .any(|error| line.contains(error)),
+                        "{line}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,461 @@
+use crate::transmitters::{self, str_time, DecodedTime, ErrorKind, MinuteReport};
+use crate::Station;
+use std::fmt;
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+
+/// Syslog severity of unusual but expected events, like a change of daylight saving time.
+pub const NOTICE: u8 = 5;
+/// Syslog severity of events which may need attention, like bad reception.
+pub const WARNING: u8 = 4;
+/// Syslog facility of system daemons.
+#[cfg(unix)]
+const FACILITY_DAEMON: u8 = 3;
+
+/// The kinds of notable events.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    /// a change of daylight saving time is announced
+    DstAnnounced,
+    /// daylight saving time changed
+    DstProcessed,
+    /// a leap second is announced
+    LeapAnnounced,
+    /// the call bit is set
+    CallBit,
+    /// DUT1 changed
+    Dut1,
+    /// minutes in a row with parity failures
+    Parity,
+    /// minutes in a row without a good minute
+    Gap,
+}
+
+impl fmt::Display for EventKind {
+    /// Write the name of the kind, as used in the structured form of the event.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            EventKind::DstAnnounced => "dst_announced",
+            EventKind::DstProcessed => "dst_processed",
+            EventKind::LeapAnnounced => "leap_announced",
+            EventKind::CallBit => "call_bit",
+            EventKind::Dut1 => "dut1",
+            EventKind::Parity => "parity",
+            EventKind::Gap => "gap",
+        })
+    }
+}
+
+/// A notable event in the broadcast or the reception of a station.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub kind: EventKind,
+    /// the syslog severity of the event
+    pub severity: u8,
+    /// 0-based index of the minute (line) in the log at which the event occurred
+    pub index: usize,
+    /// the decoded date and time of that minute, if any
+    pub time: Option<DecodedTime>,
+    /// the event in plain English
+    pub message: String,
+}
+
+impl Event {
+    /// Return the event as a single line of `key=value` pairs, the structured form used in both
+    /// syslog and the journal.
+    ///
+    /// # Arguments
+    /// * `station` - the station which was received
+    pub fn to_logfmt(&self, station: Station) -> String {
+        let mut line = format!(
+            "event={} station={station} minute={}",
+            self.kind,
+            self.index + 1
+        );
+        if let Some(s_time) = &self.time {
+            line += &format!(" time=\"{}\"", str_time(s_time));
+        }
+        line + &format!(" message={:?}", self.message)
+    }
+}
+
+/// Detection of notable events in the successive minutes of a log: changes of daylight saving
+/// time, leap seconds, the call bit and DUT1 as broadcast in good minutes, runs of minutes with
+/// parity failures and long runs of minutes without a good one.
+pub struct EventDetector {
+    station: Station,
+    /// number of minutes in a row with parity failures for a `parity` event
+    parity_threshold: u32,
+    /// number of minutes without a good minute for a `gap` event
+    gap_threshold: i64,
+    dst_announced: bool,
+    leap_announced: bool,
+    call_bit: bool,
+    dut1: Option<i8>,
+    /// number of decoded minutes in a row with parity failures so far
+    parity_run: u32,
+    /// number of minutes in a row which were not good so far
+    gap_run: i64,
+    /// time of the last good minute in seconds since 1970-01-01 00:00 UTC
+    last_good: Option<i64>,
+}
+
+impl EventDetector {
+    /// Return a detector which has not seen any minutes yet.
+    ///
+    /// # Arguments
+    /// * `station` - the station which is received
+    /// * `parity_threshold` - number of minutes in a row with parity failures to report
+    /// * `gap_threshold` - number of minutes without a good minute to report
+    pub fn new(station: Station, parity_threshold: u32, gap_threshold: i64) -> Self {
+        Self {
+            station,
+            parity_threshold,
+            gap_threshold,
+            dst_announced: false,
+            leap_announced: false,
+            call_bit: false,
+            dut1: None,
+            parity_run: 0,
+            gap_run: 0,
+            last_good: None,
+        }
+    }
+
+    /// Return the events which occurred in the given minute, which follows the minutes given
+    /// before.
+    ///
+    /// # Arguments
+    /// * `report` - the analysis of the minute
+    pub fn update(&mut self, report: &MinuteReport) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut push = |kind, severity, message: String| {
+            events.push(Event {
+                kind,
+                severity,
+                index: report.index,
+                time: report.time,
+                message,
+            })
+        };
+        if report.has_error(ErrorKind::Parity) {
+            self.parity_run += 1;
+            if self.parity_run == self.parity_threshold {
+                push(
+                    EventKind::Parity,
+                    WARNING,
+                    format!("Parity failures in {} minutes in a row", self.parity_run),
+                );
+            }
+        } else if report.time.is_some() {
+            self.parity_run = 0;
+        }
+        // flags of minutes with problems are not trusted
+        if !report.is_good() {
+            // report the gap while it lasts instead of once a good minute arrives
+            self.gap_run += 1;
+            if self.gap_run == self.gap_threshold {
+                push(
+                    EventKind::Gap,
+                    WARNING,
+                    format!("No good minute for {} minutes", self.gap_run),
+                );
+            }
+            return events;
+        }
+        let time = report.time.unwrap();
+        if let Some(s_utc) = time.unix_time(self.station) {
+            // minutes which were not received at all are only noticed now
+            if let Some(s_last) = self.last_good {
+                let missed = (s_utc - s_last) / 60 - 1;
+                if missed >= self.gap_threshold && self.gap_run < self.gap_threshold {
+                    push(
+                        EventKind::Gap,
+                        WARNING,
+                        format!("No good minute for {missed} minutes"),
+                    );
+                }
+            }
+            self.last_good = Some(s_utc);
+        }
+        self.gap_run = 0;
+        let dst = time.dst.unwrap_or(0);
+        let announced = dst & radio_datetime_utils::DST_ANNOUNCED != 0;
+        if announced && !self.dst_announced {
+            push(
+                EventKind::DstAnnounced,
+                NOTICE,
+                String::from("Daylight saving time change announced"),
+            );
+        }
+        self.dst_announced = announced;
+        if dst & radio_datetime_utils::DST_PROCESSED != 0 {
+            push(
+                EventKind::DstProcessed,
+                NOTICE,
+                format!(
+                    "Daylight saving time changed to {}",
+                    if dst & radio_datetime_utils::DST_SUMMER != 0 {
+                        "summer"
+                    } else {
+                        "winter"
+                    }
+                ),
+            );
+        }
+        let announced = time
+            .leap_second
+            .is_some_and(|leap| leap & radio_datetime_utils::LEAP_ANNOUNCED != 0);
+        if announced && !self.leap_announced {
+            push(
+                EventKind::LeapAnnounced,
+                NOTICE,
+                String::from("Leap second announced"),
+            );
+        }
+        self.leap_announced = announced;
+        let call_bit = transmitters::layout(self.station)
+            .fields
+            .iter()
+            .find(|field| field.name == "Call bit")
+            .is_some_and(|field| report.bits.chars().nth(field.start) == Some('1'));
+        if call_bit && !self.call_bit {
+            push(
+                EventKind::CallBit,
+                WARNING,
+                String::from("Call bit set, the transmitter reports an irregularity"),
+            );
+        }
+        self.call_bit = call_bit;
+        if let Some(s_dut1) = report.dut1 {
+            if self.dut1.is_some_and(|dut1| dut1 != s_dut1) {
+                push(
+                    EventKind::Dut1,
+                    NOTICE,
+                    format!("DUT1 changed to {:.1} s", s_dut1 as f64 / 10.0),
+                );
+            }
+            self.dut1 = Some(s_dut1);
+        }
+        events
+    }
+}
+
+/// The destination of the events.
+#[cfg(unix)]
+pub enum EventSink {
+    /// a syslog daemon listening on the given socket, usually `/dev/log`
+    Syslog { socket: UnixDatagram, path: String },
+    /// standard output with the severity as `<n>` prefix, as read by journald from services
+    Journal,
+}
+
+#[cfg(unix)]
+impl EventSink {
+    /// Return a sink which sends the events to the syslog daemon listening on the given socket.
+    ///
+    /// # Arguments
+    /// * `path` - the path of the socket, usually `/dev/log`
+    pub fn syslog(path: &str) -> Result<Self, String> {
+        Ok(Self::Syslog {
+            socket: UnixDatagram::unbound()
+                .map_err(|error| format!("Could not create socket : {error}"))?,
+            path: String::from(path),
+        })
+    }
+
+    /// Send or print the given event.
+    ///
+    /// # Arguments
+    /// * `station` - the station which was received
+    /// * `event` - the event to emit
+    pub fn emit(&self, station: Station, event: &Event) -> Result<(), String> {
+        match self {
+            Self::Syslog { socket, path } => socket
+                .send_to(syslog_message(station, event).as_bytes(), path)
+                .map(|_| ())
+                .map_err(|error| format!("Could not send event to '{path}' : {error}")),
+            Self::Journal => {
+                println!("<{}>{}", event.severity, event.to_logfmt(station));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Return the given event as a syslog message in the BSD format (RFC 3164) accepted on
+/// `/dev/log`, which adds the time stamp and host name itself.
+///
+/// # Arguments
+/// * `station` - the station which was received
+/// * `event` - the event to format
+#[cfg(unix)]
+fn syslog_message(station: Station, event: &Event) -> String {
+    format!(
+        "<{}>radio_datetime_analyzer[{}]: {}",
+        FACILITY_DAEMON * 8 + event.severity,
+        std::process::id(),
+        event.to_logfmt(station)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::MinuteError;
+
+    /// Return a report for the given minute of 2024-03-31 01:MM with the given state.
+    fn report(minute: u8, dst: u8, bits: &str, errors: &[(ErrorKind, &str)]) -> MinuteReport {
+        MinuteReport {
+            index: minute as usize,
+            bits: String::from(bits),
+            time: Some(DecodedTime {
+                year: Some(24),
+                month: Some(3),
+                day: Some(31),
+                weekday: Some(7),
+                hour: Some(1),
+                minute: Some(minute),
+                dst: Some(dst),
+                leap_second: Some(0),
+                ..DecodedTime::default()
+            }),
+            errors: errors
+                .iter()
+                .map(|&(kind, message)| MinuteError::new(kind, message))
+                .collect(),
+            ..MinuteReport::default()
+        }
+    }
+
+    #[test]
+    fn test_update_dcf77() {
+        let mut detector = EventDetector::new(Station::Dcf77, 2, 10);
+        let call = format!("{}1", "0".repeat(15));
+        let kinds = |events: Vec<Event>| events.iter().map(|e| e.kind).collect::<Vec<_>>();
+        assert!(detector.update(&report(0, 0, "0", &[])).is_empty());
+        let announced = radio_datetime_utils::DST_ANNOUNCED;
+        assert_eq!(
+            kinds(detector.update(&report(1, announced, &call, &[]))),
+            [EventKind::DstAnnounced, EventKind::CallBit]
+        );
+        assert!(detector
+            .update(&report(2, announced, &call, &[]))
+            .is_empty());
+        let parity = [(ErrorKind::Parity, "Hour parity bad")];
+        assert!(detector.update(&report(3, 0, "0", &parity)).is_empty());
+        assert_eq!(
+            kinds(detector.update(&report(
+                4,
+                0,
+                "0",
+                &[(ErrorKind::Parity, "Date parity undetermined")]
+            ))),
+            [EventKind::Parity]
+        );
+        assert!(detector.update(&report(5, 0, "0", &parity)).is_empty());
+        let processed = radio_datetime_utils::DST_PROCESSED;
+        let events = detector.update(&report(20, processed, "0", &[]));
+        assert_eq!(
+            kinds(events.clone()),
+            [EventKind::Gap, EventKind::DstProcessed]
+        );
+        assert_eq!(events[0].message, "No good minute for 17 minutes");
+        assert_eq!(events[1].message, "Daylight saving time changed to winter");
+    }
+
+    #[test]
+    fn test_update_gap() {
+        let mut detector = EventDetector::new(Station::Dcf77, 10, 2);
+        assert!(detector.update(&report(0, 0, "0", &[])).is_empty());
+        let undecoded = MinuteReport {
+            index: 1,
+            ..MinuteReport::default()
+        };
+        assert!(detector.update(&undecoded).is_empty());
+        let events = detector.update(&undecoded);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::Gap);
+        assert_eq!(events[0].message, "No good minute for 2 minutes");
+        assert!(detector.update(&undecoded).is_empty());
+        // reported already
+        assert!(detector.update(&report(4, 0, "0", &[])).is_empty());
+        // a new gap of minutes which were not received
+        assert_eq!(
+            detector.update(&report(7, 0, "0", &[]))[0].message,
+            "No good minute for 2 minutes"
+        );
+    }
+
+    #[test]
+    fn test_update_msf() {
+        let mut detector = EventDetector::new(Station::Msf, 3, 10);
+        let mut minute = report(0, 0, "0", &[]);
+        minute.dut1 = Some(-2);
+        assert!(detector.update(&minute).is_empty());
+        minute.dut1 = Some(-3);
+        minute.index = 1;
+        // not trusted
+        minute.errors = vec![MinuteError::new(
+            ErrorKind::Bits,
+            "Bit 52 is 1 instead of 0",
+        )];
+        assert!(detector.update(&minute).is_empty());
+        minute.errors.clear();
+        let events = detector.update(&minute);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::Dut1);
+        assert_eq!(events[0].message, "DUT1 changed to -0.3 s");
+    }
+
+    #[test]
+    fn test_format() {
+        let event = Event {
+            kind: EventKind::DstAnnounced,
+            severity: NOTICE,
+            index: 41,
+            time: report(0, 0, "", &[]).time,
+            message: String::from("Daylight saving time change \"announced\""),
+        };
+        assert_eq!(
+            event.to_logfmt(Station::Dcf77),
+            "event=dst_announced station=dcf77 minute=42 time=\"24-03-31 01:00\" \
+             message=\"Daylight saving time change \\\"announced\\\"\""
+        );
+        #[cfg(unix)]
+        assert!(syslog_message(Station::Msf, &event).starts_with(&format!(
+            "<29>radio_datetime_analyzer[{}]: event=dst_announced station=msf minute=42",
+            std::process::id()
+        )));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_emit_syslog() {
+        // stand-in for the syslog daemon
+        let path = std::env::temp_dir().join(format!("rda-log-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let daemon = UnixDatagram::bind(&path).unwrap();
+        let sink = EventSink::syslog(path.to_str().unwrap()).unwrap();
+        let event = Event {
+            kind: EventKind::Gap,
+            severity: WARNING,
+            index: 0,
+            time: None,
+            message: String::from("No good minute for 10 minutes"),
+        };
+        sink.emit(Station::Dcf77, &event).unwrap();
+        let mut buffer = [0; 256];
+        let length = daemon.recv(&mut buffer).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&buffer[..length]),
+            format!(
+                "<28>radio_datetime_analyzer[{}]: event=gap station=dcf77 minute=1 \
+                 message=\"No good minute for 10 minutes\"",
+                std::process::id()
+            )
+        );
+        assert!(sink.emit(Station::Dcf77, &event).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use crate::input::timestamped::parse_timestamp;
+use crate::transmitters::{ErrorKind, MinuteReport, ERROR_KINDS};
+use std::fmt;
+use std::str::FromStr;
+
+/// Seconds from 1970-01-01 to 2000-01-01, the epoch of `DecodedTime::minutes()`.
+const EPOCH_2000: f64 = 946_684_800.0;
+
+/// A kind of minute to select with `MinuteFilter::only`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinuteKind {
+    /// minutes with this kind of problem
+    Error(ErrorKind),
+    /// minutes with a daylight saving time event, see `has_dst_event()`
+    Dst,
+}
+
+impl FromStr for MinuteKind {
+    type Err = String;
+
+    /// Parse the name of the kind, as written by `fmt()`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "dst" {
+            return Ok(MinuteKind::Dst);
+        }
+        s.parse().map(MinuteKind::Error).map_err(|_| {
+            format!(
+                "kind must be one of {}, dst but is '{s}'",
+                ERROR_KINDS.map(|kind| kind.to_string()).join(", ")
+            )
+        })
+    }
+}
+
+impl fmt::Display for MinuteKind {
+    /// Write the name of the kind in lower case, as accepted by `from_str()`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MinuteKind::Error(s_kind) => write!(f, "{s_kind}"),
+            MinuteKind::Dst => f.write_str("dst"),
+        }
+    }
+}
+
+/// Selection of the minutes to show.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MinuteFilter {
+    /// only show minutes with problems
+    pub only_errors: bool,
+    /// only show minutes of these kinds, all minutes if empty
+    pub only: Vec<MinuteKind>,
+    /// only show minutes decoded at or after this time, in minutes since 2000-01-01 00:00
+    pub from: Option<i64>,
+    /// only show minutes decoded at or before this time, in minutes since 2000-01-01 00:00
+    pub to: Option<i64>,
+}
+
+impl MinuteFilter {
+    /// Return if the given minute should be shown. With a time window, only minutes with a
+    /// completely decoded time are shown.
+    ///
+    /// # Arguments
+    /// * `report` - the analysis of the minute
+    pub fn matches(&self, report: &MinuteReport) -> bool {
+        if self.only_errors && report.errors.is_empty() {
+            return false;
+        }
+        if !self.only.is_empty()
+            && !self.only.iter().any(|kind| match kind {
+                MinuteKind::Error(s_kind) => report.has_error(*s_kind),
+                MinuteKind::Dst => has_dst_event(report),
+            })
+        {
+            return false;
+        }
+        if self.from.is_some() || self.to.is_some() {
+            let Some(minutes) = report.time.and_then(|time| time.minutes()) else {
+                return false;
+            };
+            if self.from.is_some_and(|from| minutes < from)
+                || self.to.is_some_and(|to| minutes > to)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Return if daylight saving time is announced, processed or jumped in the given minute.
+///
+/// # Arguments
+/// * `report` - the analysis of the minute
+fn has_dst_event(report: &MinuteReport) -> bool {
+    report.time.and_then(|time| time.dst).is_some_and(|dst| {
+        dst & (radio_datetime_utils::DST_ANNOUNCED
+            | radio_datetime_utils::DST_PROCESSED
+            | radio_datetime_utils::DST_JUMP)
+            != 0
+    })
+}
+
+/// Parse the comma-separated kinds of minutes for `MinuteFilter::only`.
+///
+/// # Arguments
+/// * `kinds` - the kinds to parse, e.g. `parity,jump`
+pub fn parse_kinds(kinds: &str) -> Result<Vec<MinuteKind>, String> {
+    kinds.split(',').map(str::parse).collect()
+}
+
+/// Parse a time for `MinuteFilter::from` or `MinuteFilter::to`, as an ISO 8601 date and time in
+/// the local time of the station like `2024-03-31 01:59`.
+///
+/// # Arguments
+/// * `time` - the time to parse
+pub fn parse_time(time: &str) -> Option<i64> {
+    // plain numbers would be seconds since 1970-01-01
+    if !time.contains('-') {
+        return None;
+    }
+    Some(((parse_timestamp(time)? - EPOCH_2000) / 60.0).floor() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::{DecodedTime, MinuteError};
+
+    /// Return a report for the given minute after 2024-03-31 01:00 with the given problems.
+    fn report(minute: u8, dst: u8, errors: &[(ErrorKind, &str)]) -> MinuteReport {
+        MinuteReport {
+            time: Some(DecodedTime {
+                year: Some(24),
+                month: Some(3),
+                day: Some(31),
+                hour: Some(1),
+                minute: Some(minute),
+                dst: Some(dst),
+                ..DecodedTime::default()
+            }),
+            errors: errors
+                .iter()
+                .map(|&(kind, message)| MinuteError::new(kind, message))
+                .collect(),
+            ..MinuteReport::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_kinds() {
+        assert_eq!(
+            parse_kinds("parity,dst"),
+            Ok(vec![MinuteKind::Error(ErrorKind::Parity), MinuteKind::Dst])
+        );
+        assert!(parse_kinds("parity,foo").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("2000-01-01 00:00"), Some(0));
+        assert_eq!(parse_time("2024-03-31T01:59"), Some(12_752_759));
+        assert_eq!(parse_time("12"), None);
+        assert_eq!(parse_time("2024-03-31"), None);
+    }
+
+    #[test]
+    fn test_matches() {
+        let good = report(0, 0, &[]);
+        let parity = report(1, 0, &[(ErrorKind::Parity, "Hour parity bad")]);
+        let dst = report(2, radio_datetime_utils::DST_ANNOUNCED, &[]);
+        let undecoded = MinuteReport {
+            errors: vec![MinuteError::new(
+                ErrorKind::Length,
+                "Minute is 3 seconds instead of 60 seconds long",
+            )],
+            ..MinuteReport::default()
+        };
+        let all = MinuteFilter::default();
+        assert!([&good, &parity, &dst, &undecoded]
+            .iter()
+            .all(|report| all.matches(report)));
+        let only_errors = MinuteFilter {
+            only_errors: true,
+            ..MinuteFilter::default()
+        };
+        assert!(!only_errors.matches(&good));
+        assert!(only_errors.matches(&parity));
+        assert!(only_errors.matches(&undecoded));
+        let only = MinuteFilter {
+            only: vec![MinuteKind::Error(ErrorKind::Length), MinuteKind::Dst],
+            ..MinuteFilter::default()
+        };
+        assert!(!only.matches(&good));
+        assert!(!only.matches(&parity));
+        assert!(only.matches(&dst));
+        assert!(only.matches(&undecoded));
+        let window = MinuteFilter {
+            from: parse_time("2024-03-31 01:01"),
+            to: parse_time("2024-03-31 01:01"),
+            ..MinuteFilter::default()
+        };
+        assert!(!window.matches(&good));
+        assert!(window.matches(&parity));
+        assert!(!window.matches(&dst));
+        assert!(!window.matches(&undecoded));
+    }
+}
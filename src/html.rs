@@ -0,0 +1,225 @@
+use crate::transmitters::{self, str_time, FieldRole, MinuteReport};
+use crate::Station;
+
+/// Style sheet of the report, embedded so that the report works without external assets.
+const STYLE: &str = "\
+body { font-family: sans-serif; }
+table { border-collapse: collapse; }
+th, td { padding: 1px 3px; }
+table.bits td { font-family: monospace; text-align: center; }
+table.bits td.result { font-family: sans-serif; text-align: left; }
+td.marker { background: #d8d8d8; }
+td.flag { background: #ffe2a8; }
+td.data { background: #dcdcff; }
+td.time { background: #c8f0c8; }
+td.parity { background: #a8d4ff; }
+td.missing { background: #ff8c8c; }
+.error { color: #c00000; }
+";
+
+/// Return a standalone HTML report of the analysis, with a grid of the bits of each minute
+/// colored by the role of their field, the results of each minute and a summary.
+///
+/// # Arguments
+/// * `station` - the station which was logged
+/// * `reports` - the analysis of each minute of the log
+/// * `title` - the title of the report, e.g. the name of the log file
+pub fn render(station: Station, reports: &[MinuteReport], title: &str) -> String {
+    let title = escape(title);
+    let mut res = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>\n{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+    );
+    res += &summary(reports);
+    let seconds = reports
+        .iter()
+        .map(|report| report.bits.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(60);
+    res += "<h2>Minutes</h2>\n<table class=\"bits\">\n<tr><th>Minute</th>";
+    for second in 0..seconds {
+        res += &format!("<th>{second}</th>");
+    }
+    res += "<th>Result</th></tr>\n";
+    let layout = transmitters::layout(station);
+    for report in reports {
+        res += &format!("<tr><td>{}</td>", report.index + 1);
+        let minute_length = report.bits.chars().count();
+        let bits = report.bits.chars().map(Some).chain(std::iter::repeat(None));
+        for (second, bit) in bits.take(seconds).enumerate() {
+            match bit {
+                Some('_') => res += "<td class=\"missing\">_</td>",
+                Some(s_bit) => {
+                    let class = match layout.role_at(second, minute_length) {
+                        Some(FieldRole::Marker) => "marker",
+                        Some(FieldRole::Flag) => "flag",
+                        Some(FieldRole::Data) => "data",
+                        Some(FieldRole::Time) => "time",
+                        Some(FieldRole::Parity) => "parity",
+                        None => "",
+                    };
+                    res += &format!("<td class=\"{class}\">{}</td>", escape(&s_bit.to_string()));
+                }
+                None => res += "<td></td>",
+            }
+        }
+        res += "<td class=\"result\">";
+        res += &match report.time {
+            Some(s_time) => str_time(&s_time),
+            None => String::from("not decoded"),
+        };
+        for detail in report.details() {
+            res += &format!("<br>{}", escape(detail));
+        }
+        for error in &report.errors {
+            res += &format!(
+                "<br><span class=\"error\">{}</span>",
+                escape(&error.message)
+            );
+        }
+        res += "</td></tr>\n";
+    }
+    res += "</table>\n</body>\n</html>\n";
+    res
+}
+
+/// Return the summary statistics of the analysis as HTML: the number of (good) minutes and how
+/// often each problem occurred.
+///
+/// # Arguments
+/// * `reports` - the analysis of each minute of the log
+fn summary(reports: &[MinuteReport]) -> String {
+    let good = reports.iter().filter(|report| report.is_good()).count();
+    let decoded = reports
+        .iter()
+        .filter(|report| report.time.is_some())
+        .count();
+    let mut res = format!(
+        "<h2>Summary</h2>\n<table>\n<tr><td>Minutes</td><td>{}</td></tr>\n\
+         <tr><td>Decoded minutes</td><td>{decoded}</td></tr>\n\
+         <tr><td>Good minutes</td><td>{good}</td></tr>\n</table>\n",
+        reports.len()
+    );
+    let mut problems: Vec<(&str, usize)> = Vec::new();
+    for error in reports.iter().flat_map(|report| report.error_messages()) {
+        match problems.iter_mut().find(|(p_error, _)| *p_error == error) {
+            Some(s_problem) => s_problem.1 += 1,
+            None => problems.push((error, 1)),
+        }
+    }
+    if !problems.is_empty() {
+        res += "<h2>Problems</h2>\n<table>\n";
+        for (error, count) in problems {
+            res += &format!(
+                "<tr><td class=\"error\">{}</td><td>{count}</td></tr>\n",
+                escape(error)
+            );
+        }
+        res += "</table>\n";
+    }
+    res
+}
+
+/// Return the given text with the characters which are special in HTML escaped.
+///
+/// # Arguments
+/// * `text` - the text to escape
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::{dcf77, DecodedTime, ErrorKind, MinuteError};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a<b & \"c\">"), "a&lt;b &amp; &quot;c&quot;&gt;");
+    }
+
+    #[test]
+    fn test_render() {
+        let reports = vec![
+            MinuteReport {
+                index: 0,
+                bits: String::from("0_0000000000000000101000110111100011100011101010011001100110"),
+                time: Some(DecodedTime {
+                    year: Some(0),
+                    month: Some(1),
+                    day: Some(1),
+                    hour: Some(0),
+                    minute: Some(0),
+                    ..DecodedTime::default()
+                }),
+                lines: vec![
+                    String::from("0 _0000000000000 0 001 0 1 0000000 0 000000 0 ...\n"),
+                    String::from("first_minute=true seconds=60\n"),
+                    String::from("Minute parity bad\n"),
+                    String::from("\n"),
+                ],
+                errors: vec![MinuteError {
+                    line: Some(2),
+                    ..MinuteError::bad_parity(0, &dcf77::LAYOUT.parities[0])
+                }],
+                ..MinuteReport::default()
+            },
+            MinuteReport {
+                index: 1,
+                bits: String::from("0101"),
+                lines: vec![
+                    String::from("0 101\n"),
+                    String::from("Minute is 4 seconds instead of 59 seconds long\n"),
+                    String::from("\n"),
+                ],
+                errors: vec![MinuteError {
+                    line: Some(1),
+                    ..MinuteError::new(
+                        ErrorKind::Length,
+                        "Minute is 4 seconds instead of 59 seconds long",
+                    )
+                }],
+                ..MinuteReport::default()
+            },
+        ];
+        let html = render(Station::Dcf77, &reports, "<log>");
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>&lt;log&gt;</title>"));
+        assert!(html.contains("<tr><td>Minutes</td><td>2</td></tr>"));
+        assert!(html.contains("<tr><td>Good minutes</td><td>0</td></tr>"));
+        assert!(html.contains("<tr><td class=\"error\">Minute parity bad</td><td>1</td></tr>"));
+        assert!(html.contains(
+            "<tr><td>1</td><td class=\"marker\">0</td><td class=\"missing\">_</td>\
+             <td class=\"data\">0</td>"
+        ));
+        assert!(html.contains("<td class=\"parity\">"));
+        assert!(html.contains(
+            "00-01-01 00:00<br>first_minute=true seconds=60\
+             <br><span class=\"error\">Minute parity bad</span>"
+        ));
+        assert!(html.contains(
+            "<td></td><td class=\"result\">not decoded<br><span class=\"error\">Minute is 4"
+        ));
+        assert!(!html.contains("http"));
+    }
+
+    #[test]
+    fn test_render_msf_parities() {
+        let reports = vec![MinuteReport {
+            bits: String::from("400000000000000000000000000000000000000000000000000001111110"),
+            ..MinuteReport::default()
+        }];
+        let html = render(Station::Msf, &reports, "msf");
+        // the parity bits in the B channel are part of the end-of-minute marker
+        assert!(html.contains(
+            "<td class=\"marker\">0</td><td class=\"marker\">1</td>\
+             <td class=\"parity\">1</td><td class=\"parity\">1</td>\
+             <td class=\"parity\">1</td><td class=\"parity\">1</td>\
+             <td class=\"marker\">1</td><td class=\"marker\">0</td><td class=\"result\">"
+        ));
+    }
+}
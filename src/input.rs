@@ -0,0 +1,7 @@
+pub mod gpio;
+pub mod pulses;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod serial;
+pub mod stream;
+pub mod timestamped;
+pub mod wav;
@@ -0,0 +1,326 @@
+use crate::input::pulses;
+use crate::input::timestamped::parse_timestamp;
+use crate::Station;
+
+/// Size in bytes of a `struct gpio_v2_line_event` as read from a GPIO character device.
+pub(crate) const EVENT_SIZE: usize = 48;
+/// Dips and gaps between dips shorter than this many milliseconds are taken as glitches.
+const GLITCH: f64 = 10.0;
+
+/// A change of the level of a GPIO line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Edge {
+    /// the time of the change in seconds, from any epoch
+    pub time: f64,
+    /// if the level went from low to high
+    pub rising: bool,
+}
+
+/// Read the edge events of a GPIO line, either as a binary dump of the `gpio_v2_line_event`
+/// structures read from the character device or as the text output of `gpiomon`.
+///
+/// Each line of text holds one event with its timestamp, like
+/// `1711846860.123456789 rising gpiochip0 17` (libgpiod 2) or
+/// `event:  RISING EDGE offset: 17 timestamp: [1711846860.123456789]` (libgpiod 1). Empty
+/// lines and lines starting with # are ignored, as is an incomplete last event which is still
+/// being written.
+///
+/// # Arguments
+/// * `data` - the recorded events
+pub fn read_events(data: &[u8]) -> Result<Vec<Edge>, String> {
+    // text never contains NUL bytes, the binary structures always do
+    if data.contains(&0) {
+        return Ok(binary_events(data));
+    }
+    let text = String::from_utf8_lossy(data);
+    let mut edges = Vec::new();
+    for (idx, line) in text.split_inclusive('\n').enumerate() {
+        let event = line.trim();
+        if event.is_empty() || event.starts_with('#') {
+            continue;
+        }
+        match parse_event(event) {
+            Some(s_edge) => edges.push(s_edge),
+            None if !line.ends_with('\n') => {}
+            None => return Err(format!("line {}: invalid event '{event}'", idx + 1)),
+        }
+    }
+    Ok(edges)
+}
+
+/// Return the edges of the given binary dump of `gpio_v2_line_event` structures, without an
+/// incomplete last event.
+///
+/// # Arguments
+/// * `data` - the recorded events
+fn binary_events(data: &[u8]) -> Vec<Edge> {
+    data.chunks_exact(EVENT_SIZE)
+        .map(|event| Edge {
+            time: u64::from_le_bytes(event[0..8].try_into().unwrap()) as f64 / 1e9,
+            // GPIO_V2_LINE_EVENT_RISING_EDGE
+            rising: u32::from_le_bytes(event[8..12].try_into().unwrap()) == 1,
+        })
+        .collect()
+}
+
+/// Parse a line of `gpiomon` output, return None if it is invalid.
+///
+/// # Arguments
+/// * `event` - the line to parse
+fn parse_event(event: &str) -> Option<Edge> {
+    let lower = event.to_lowercase();
+    let rising = if lower.contains("rising") {
+        true
+    } else if lower.contains("falling") {
+        false
+    } else {
+        return None;
+    };
+    // libgpiod 1 shows the offset of the line before the timestamp
+    let timestamp = match event.split_once("timestamp:") {
+        Some((_, s_rest)) => s_rest,
+        None => event,
+    }
+    .split(|c: char| c.is_whitespace() || c == '[' || c == ']')
+    .find(|token| !token.is_empty())?;
+    Some(Edge {
+        time: parse_timestamp(timestamp)?,
+        rising,
+    })
+}
+
+/// Return a log of pulse widths in milliseconds as consumed by `pulses::to_bit_log()`, for the
+/// given edges of the output of a receiver. Glitches shorter than 10 ms are ignored.
+///
+/// # Arguments
+/// * `edges` - the edges of the output, in order
+/// * `station` - the station which was received
+/// * `inverted` - if the carrier dips are represented by a low instead of a high level
+pub fn to_pulse_log(edges: &[Edge], station: Station, inverted: bool) -> String {
+    let mut converter = Converter::new(station, inverted);
+    let mut res: String = edges
+        .iter()
+        .map(|&edge| converter.push_edge(edge))
+        .collect();
+    res += &converter.finish();
+    res
+}
+
+/// Converter of edge events which are fed as they arrive into a pulse width log, see
+/// `read_events()` and `to_pulse_log()`.
+pub struct Converter {
+    inverted: bool,
+    /// number of lines of text converted so far, for the error messages
+    lines: usize,
+    /// the start time of the current dip in milliseconds, if any
+    dip_start: Option<f64>,
+    /// the last dip, which is continued if the carrier comes back for a moment only
+    last_dip: Option<(f64, f64)>,
+    writer: pulses::DipWriter,
+}
+
+impl Converter {
+    /// Return a new converter, at the start of the events.
+    ///
+    /// # Arguments
+    /// * `station` - the station which was received
+    /// * `inverted` - if the carrier dips are represented by a low instead of a high level
+    pub fn new(station: Station, inverted: bool) -> Self {
+        Self {
+            inverted,
+            lines: 0,
+            dip_start: None,
+            last_dip: None,
+            writer: pulses::DipWriter::new(station),
+        }
+    }
+
+    /// Convert the given complete events, either binary or lines of text like `read_events()`,
+    /// return the lines they add to the pulse width log.
+    ///
+    /// # Arguments
+    /// * `data` - the next recorded events
+    pub fn push_events(&mut self, data: &[u8]) -> Result<String, String> {
+        let edges = if data.contains(&0) {
+            binary_events(data)
+        } else {
+            let mut edges = Vec::new();
+            for line in String::from_utf8_lossy(data).lines() {
+                self.lines += 1;
+                let event = line.trim();
+                if event.is_empty() || event.starts_with('#') {
+                    continue;
+                }
+                edges.push(
+                    parse_event(event)
+                        .ok_or(format!("line {}: invalid event '{event}'", self.lines))?,
+                );
+            }
+            edges
+        };
+        Ok(edges.into_iter().map(|edge| self.push_edge(edge)).collect())
+    }
+
+    /// Add the given edge, return the lines of the pulse width log which are complete now.
+    /// Glitches shorter than 10 ms are ignored.
+    ///
+    /// # Arguments
+    /// * `edge` - the next edge of the output of the receiver
+    pub fn push_edge(&mut self, edge: Edge) -> String {
+        let time = edge.time * 1000.0;
+        if edge.rising != self.inverted {
+            self.dip_start = Some(time);
+            return String::from("");
+        }
+        let Some(s_start) = self.dip_start.take() else {
+            return String::from("");
+        };
+        match &mut self.last_dip {
+            // the carrier came back for a moment only, continue the last dip
+            Some(s_last) if s_start - s_last.1 < GLITCH => s_last.1 = time,
+            _ if time - s_start < GLITCH => {}
+            _ => {
+                if let Some((last_start, last_end)) = self.last_dip.replace((s_start, time)) {
+                    return self.writer.push(last_start, last_end);
+                }
+            }
+        }
+        String::from("")
+    }
+
+    /// Return the rest of the pulse width log, at the end of the events.
+    pub fn finish(&mut self) -> String {
+        let mut res = String::from("");
+        if let Some((last_start, last_end)) = self.last_dip.take() {
+            res += &self.writer.push(last_start, last_end);
+        }
+        res + &self.writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::pulses::PulseThresholds;
+
+    /// Return the binary `gpio_v2_line_event` of the given edge at the given time in ns.
+    fn binary_event(time: u64, rising: bool, seqno: u32) -> Vec<u8> {
+        let mut event = Vec::new();
+        event.extend_from_slice(&time.to_le_bytes());
+        event.extend_from_slice(&(if rising { 1u32 } else { 2u32 }).to_le_bytes());
+        event.extend_from_slice(&17u32.to_le_bytes()); // offset
+        event.extend_from_slice(&seqno.to_le_bytes());
+        event.extend_from_slice(&seqno.to_le_bytes()); // line_seqno
+        event.extend_from_slice(&[0; 24]); // padding
+        event
+    }
+
+    #[test]
+    fn test_read_events_text() {
+        assert_eq!(
+            read_events(
+                b"# gpiomon -c gpiochip0 17\n\
+                  1711846860.500000000\trising\tgpiochip0 17\n\
+                  event: FALLING EDGE offset: 17 timestamp: [1711846860.600000000]\n\
+                  \n\
+                  1711846861.5 rising\n\
+                  1711846861"
+            ),
+            Ok(vec![
+                Edge {
+                    time: 1_711_846_860.5,
+                    rising: true
+                },
+                Edge {
+                    time: 1_711_846_860.6,
+                    rising: false
+                },
+                Edge {
+                    time: 1_711_846_861.5,
+                    rising: true
+                }
+            ])
+        );
+        assert_eq!(
+            read_events(b"1711846860.5 rising\nfoo\n"),
+            Err(String::from("line 2: invalid event 'foo'"))
+        );
+    }
+
+    #[test]
+    fn test_read_events_binary() {
+        let mut data = binary_event(2_500_000_000, true, 1);
+        data.extend(binary_event(2_700_000_000, false, 2));
+        data.extend(&binary_event(3_500_000_000, true, 3)[..20]);
+        assert_eq!(
+            read_events(&data),
+            Ok(vec![
+                Edge {
+                    time: 2.5,
+                    rising: true
+                },
+                Edge {
+                    time: 2.7,
+                    rising: false
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_to_pulse_log() {
+        let edge = |time, rising| Edge { time, rising };
+        let edges = [
+            edge(0.5, true),
+            edge(0.6, false),
+            edge(1.5, true),
+            edge(1.6, false),
+            edge(1.605, true), // glitch
+            edge(1.7, false),
+            edge(2.1, true), // glitch
+            edge(2.102, false),
+            edge(3.5, true),
+            edge(3.6, false),
+        ];
+        assert_eq!(
+            to_pulse_log(&edges, Station::Dcf77, false),
+            "100\n200\n1800 # pause\n100\n"
+        );
+        assert_eq!(to_pulse_log(&edges[..4], Station::Dcf77, true), "900\n");
+    }
+
+    #[test]
+    fn test_msf_round_trip() {
+        const BITS: &str = "400000000220000000010000000011101000110100011101100101133110";
+        let mut data = Vec::new();
+        let mut seqno = 0;
+        let mut push_dip = |start: u64, width: u64| {
+            for (time, rising) in [(start, true), (start + width, false)] {
+                seqno += 1;
+                data.extend(binary_event(time * 1_000_000, rising, seqno));
+            }
+        };
+        for (second, bit) in BITS.chars().enumerate() {
+            let start = 1_000_000 + second as u64 * 1000;
+            match bit {
+                '4' => push_dip(start, 500),
+                '2' => {
+                    push_dip(start, 100);
+                    push_dip(start + 200, 100);
+                }
+                '0' => push_dip(start, 100),
+                '1' => push_dip(start, 200),
+                _ => push_dip(start, 300),
+            }
+        }
+        let pulse_log = to_pulse_log(&read_events(&data).unwrap(), Station::Msf, false);
+        assert_eq!(
+            pulses::to_bit_log(
+                &pulse_log,
+                Station::Msf,
+                &PulseThresholds::new(Station::Msf)
+            ),
+            Ok(format!("{BITS}\n"))
+        );
+    }
+}
@@ -0,0 +1,370 @@
+use crate::Station;
+
+/// Thresholds to classify measured pulse widths into symbols.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PulseThresholds {
+    /// nominal pulse widths in milliseconds and the symbols they represent
+    pub widths: Vec<(u32, char)>,
+    /// maximum deviation in milliseconds of a pulse from its nominal width
+    pub tolerance: u32,
+    /// minimum width in milliseconds of the pause which marks a new minute, if any
+    pub minute_gap: Option<u32>,
+}
+
+impl PulseThresholds {
+    /// Return the default thresholds for the given station.
+    ///
+    /// For MSF, a second with A=0 and B=1 consists of two 100 ms pulses which are written as
+    /// `100+100` in the log.
+    ///
+    /// # Arguments
+    /// * `station` - the station to return the thresholds for
+    pub fn new(station: Station) -> Self {
+        match station {
+            Station::Dcf77 => Self {
+                widths: vec![(100, '0'), (200, '1')],
+                tolerance: 40,
+                minute_gap: Some(1500),
+            },
+            Station::Msf => Self {
+                widths: vec![(100, '0'), (200, '1'), (300, '3'), (500, '4')],
+                tolerance: 40,
+                minute_gap: None,
+            },
+        }
+    }
+
+    /// Set the nominal width of the given symbol, replacing any existing width for it.
+    ///
+    /// # Arguments
+    /// * `symbol` - the symbol to set the width of
+    /// * `width` - the nominal width in milliseconds
+    pub fn set_width(&mut self, symbol: char, width: u32) {
+        self.widths.retain(|&(_, w_symbol)| w_symbol != symbol);
+        self.widths.push((width, symbol));
+    }
+
+    /// Return the symbol for the given pulse width or `_` if it is out of tolerance.
+    ///
+    /// # Arguments
+    /// * `width` - the measured pulse width in milliseconds
+    fn classify(&self, width: u32) -> char {
+        self.widths
+            .iter()
+            .filter(|&&(w_width, _)| w_width.abs_diff(width) <= self.tolerance)
+            .min_by_key(|&&(w_width, _)| w_width.abs_diff(width))
+            .map_or('_', |&(_, symbol)| symbol)
+    }
+}
+
+/// Convert a log of measured pulse widths in milliseconds into a log with one line of bits per
+/// minute, as consumed by `dcf77::analyze_buffer()` and `msf::analyze_buffer()`.
+///
+/// Pulse widths are separated by whitespace, # starts a comment. Pulses which are out of
+/// tolerance are marked as `_`. A new minute starts after the minute gap for DCF77 and at the
+/// `4` (begin-of-minute) symbol for MSF.
+///
+/// # Arguments
+/// * `buffer` - the buffer containing the pulse width log
+/// * `station` - the station which was logged
+/// * `thresholds` - the thresholds to classify the pulses
+pub fn to_bit_log(
+    buffer: &str,
+    station: Station,
+    thresholds: &PulseThresholds,
+) -> Result<String, String> {
+    let mut converter = Converter::new(station, thresholds);
+    let mut res = String::from("");
+    for line in buffer.lines() {
+        res += &converter.push_line(line)?;
+    }
+    res += converter.finish();
+    Ok(res)
+}
+
+/// Converter of a pulse width log which is fed line by line as it grows, see `to_bit_log()`.
+pub struct Converter {
+    station: Station,
+    thresholds: PulseThresholds,
+    /// number of lines converted so far, for the error messages
+    lines: usize,
+    /// if a minute was started and not ended yet
+    in_minute: bool,
+}
+
+impl Converter {
+    /// Return a new converter, at the start of a log.
+    ///
+    /// # Arguments
+    /// * `station` - the station which was logged
+    /// * `thresholds` - the thresholds to classify the pulses
+    pub fn new(station: Station, thresholds: &PulseThresholds) -> Self {
+        Self {
+            station,
+            thresholds: thresholds.clone(),
+            lines: 0,
+            in_minute: false,
+        }
+    }
+
+    /// Convert the next line of the log, return the bits and newlines it adds to the log of bits.
+    ///
+    /// # Arguments
+    /// * `line` - the next line of the pulse width log, without the newline
+    pub fn push_line(&mut self, line: &str) -> Result<String, String> {
+        self.lines += 1;
+        let mut res = String::from("");
+        for (_, token) in tokens(line) {
+            let widths = parse_widths(token).ok_or(format!(
+                "line {}: invalid pulse width '{token}'",
+                self.lines
+            ))?;
+            let symbol = match widths[..] {
+                [width] if self.thresholds.minute_gap.is_some_and(|gap| width >= gap) => '\n',
+                [width] => self.thresholds.classify(width),
+                [first, second]
+                    if self.station == Station::Msf
+                        && self.thresholds.classify(first) == '0'
+                        && self.thresholds.classify(second) == '0' =>
+                {
+                    '2'
+                }
+                _ => '_',
+            };
+            if symbol == '4' && self.in_minute {
+                res.push('\n');
+            }
+            res.push(symbol);
+            self.in_minute = symbol != '\n';
+        }
+        Ok(res)
+    }
+
+    /// Return the newline which ends the last minute at the end of the log, for MSF only as
+    /// DCF77 minutes end at the minute gap.
+    pub fn finish(&self) -> &'static str {
+        if self.station == Station::Msf && self.in_minute {
+            "\n"
+        } else {
+            ""
+        }
+    }
+}
+
+/// Return a log of pulse widths in milliseconds as consumed by `to_bit_log()`, for the given dips
+/// of the carrier.
+///
+/// For MSF, two dips within one second are written as one `a+b` pulse. For DCF77, pauses of more
+/// than 1.5 seconds are written as well, to mark the minute gap.
+///
+/// # Arguments
+/// * `dips` - the (start, end) times of the dips in milliseconds, in order
+/// * `station` - the station which was received
+pub fn from_dips(dips: &[(f64, f64)], station: Station) -> String {
+    let mut writer = DipWriter::new(station);
+    let mut res: String = dips
+        .iter()
+        .map(|&(start, end)| writer.push(start, end))
+        .collect();
+    res += &writer.finish();
+    res
+}
+
+/// Writer of a pulse width log for dips which are fed as they are found, see `from_dips()`.
+pub struct DipWriter {
+    station: Station,
+    /// the last dip, None after the second part of an MSF pulse pair
+    last_dip: Option<(f64, f64)>,
+    /// the line of the last MSF pulse, which may get a second part yet
+    pending: String,
+}
+
+impl DipWriter {
+    /// Return a new writer, at the start of a log.
+    ///
+    /// # Arguments
+    /// * `station` - the station which was received
+    pub fn new(station: Station) -> Self {
+        Self {
+            station,
+            last_dip: None,
+            pending: String::from(""),
+        }
+    }
+
+    /// Add the given dip, return the lines of the pulse width log which are complete now.
+    ///
+    /// # Arguments
+    /// * `start` - the start time of the dip in milliseconds
+    /// * `end` - the end time of the dip in milliseconds
+    pub fn push(&mut self, start: f64, end: f64) -> String {
+        let mut res = String::from("");
+        if let Some((last_start, last_end)) = self.last_dip {
+            if self.station == Station::Msf && start - last_start < 400.0 {
+                // second part of an A=0 B=1 pulse pair, the first part is pending
+                self.pending += &format!("+{:.0}", end - start);
+                self.last_dip = None;
+                return res;
+            }
+            if self.station == Station::Dcf77 && start - last_end > 1500.0 {
+                res.push_str(&format!("{:.0} # pause\n", start - last_end));
+            }
+        }
+        res += &self.finish();
+        self.pending = format!("{:.0}", end - start);
+        if self.station == Station::Dcf77 {
+            // DCF77 pulses consist of a single part
+            res += &self.finish();
+        }
+        self.last_dip = Some((start, end));
+        res
+    }
+
+    /// Return the line of the last pulse if it is not written yet, at the end of the log.
+    pub fn finish(&mut self) -> String {
+        if self.pending.is_empty() {
+            String::from("")
+        } else {
+            std::mem::take(&mut self.pending) + "\n"
+        }
+    }
+}
+
+/// Return the distribution of the pulse widths in the log as text, in buckets of the given
+/// size. Pulses consisting of two parts are counted per part.
+///
+/// # Arguments
+/// * `buffer` - the buffer containing the pulse width log
+/// * `bucket_size` - size of each bucket in milliseconds
+pub fn str_width_distribution(buffer: &str, bucket_size: u32) -> Vec<String> {
+    let mut buckets: Vec<(u32, usize)> = Vec::new();
+    for width in tokens(buffer)
+        .filter_map(|(_, token)| parse_widths(token))
+        .flatten()
+    {
+        let bucket = width / bucket_size * bucket_size;
+        match buckets.binary_search_by_key(&bucket, |&(b_bucket, _)| b_bucket) {
+            Ok(pos) => buckets[pos].1 += 1,
+            Err(pos) => buckets.insert(pos, (bucket, 1)),
+        }
+    }
+    buckets
+        .iter()
+        .map(|&(bucket, count)| format!("{bucket}-{} ms: {count}", bucket + bucket_size - 1))
+        .collect()
+}
+
+/// Return an iterator over the line index and text of each pulse width in the log.
+///
+/// # Arguments
+/// * `buffer` - the buffer containing the pulse width log
+fn tokens(buffer: &str) -> impl Iterator<Item = (usize, &str)> {
+    buffer.lines().enumerate().flat_map(|(idx, line)| {
+        line.split('#')
+            .next()
+            .unwrap()
+            .split_whitespace()
+            .map(move |token| (idx, token))
+    })
+}
+
+/// Parse a pulse width like `95`, `205.5` or `100+100` into its parts, rounded to milliseconds.
+///
+/// # Arguments
+/// * `token` - the text of the pulse width
+fn parse_widths(token: &str) -> Option<Vec<u32>> {
+    token
+        .split('+')
+        .map(|part| {
+            part.parse::<f64>()
+                .ok()
+                .filter(|width| (0.0..=u32::MAX as f64).contains(width))
+                .map(|width| width.round() as u32)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_bit_log_dcf77() {
+        assert_eq!(
+            to_bit_log(
+                "95 205 1850\n110 180 # comment\n250 50 1900 100",
+                Station::Dcf77,
+                &PulseThresholds::new(Station::Dcf77)
+            ),
+            Ok(String::from("01\n01__\n0"))
+        );
+    }
+
+    #[test]
+    fn test_to_bit_log_msf() {
+        assert_eq!(
+            to_bit_log(
+                "520 100 210 295 100+100 90+300 480 100",
+                Station::Msf,
+                &PulseThresholds::new(Station::Msf)
+            ),
+            Ok(String::from("40132_\n40\n"))
+        );
+    }
+
+    #[test]
+    fn test_to_bit_log_custom_thresholds() {
+        let mut thresholds = PulseThresholds::new(Station::Dcf77);
+        thresholds.set_width('1', 250);
+        thresholds.tolerance = 20;
+        thresholds.minute_gap = Some(1000);
+        assert_eq!(
+            to_bit_log("100 125 255 200 1000", Station::Dcf77, &thresholds),
+            Ok(String::from("0_1_\n"))
+        );
+    }
+
+    #[test]
+    fn test_to_bit_log_errors() {
+        let thresholds = PulseThresholds::new(Station::Dcf77);
+        assert!(to_bit_log("100 abc", Station::Dcf77, &thresholds).is_err());
+        assert!(to_bit_log("100 -5", Station::Dcf77, &thresholds).is_err());
+        assert!(to_bit_log("100+", Station::Dcf77, &thresholds).is_err());
+    }
+
+    #[test]
+    fn test_from_dips() {
+        assert_eq!(
+            from_dips(
+                &[(0.0, 100.0), (1000.0, 1200.0), (3000.0, 3100.2)],
+                Station::Dcf77
+            ),
+            "100\n200\n1800 # pause\n100\n"
+        );
+        assert_eq!(
+            from_dips(
+                &[
+                    (0.0, 500.0),
+                    (1000.0, 1100.0),
+                    (1200.0, 1300.0),
+                    (2000.0, 2300.0)
+                ],
+                Station::Msf
+            ),
+            "500\n100+100\n300\n"
+        );
+    }
+
+    #[test]
+    fn test_str_width_distribution() {
+        assert_eq!(
+            str_width_distribution("95 105 99 203 100+100 1850", 10),
+            vec![
+                "90-99 ms: 2",
+                "100-109 ms: 3",
+                "200-209 ms: 1",
+                "1850-1859 ms: 1"
+            ]
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+use std::process::Command;
+
+/// A receiver which sends its symbols over a serial port, in the same format as a log file.
+pub struct Serial {
+    device: String,
+    baud: u32,
+    /// the opened device, None if it still has to be (re)opened
+    file: Option<File>,
+}
+
+impl Serial {
+    /// Return a serial input on the given device, which is opened on the first read.
+    ///
+    /// # Arguments
+    /// * `device` - the path of the serial device, e.g. `/dev/ttyUSB0`
+    /// * `baud` - the speed of the serial port
+    pub fn new(device: &str, baud: u32) -> Self {
+        Self {
+            device: String::from(device),
+            baud,
+            file: None,
+        }
+    }
+
+    /// Return the symbols which arrived since the last call, waiting for at most 100 ms. The
+    /// device is opened if needed, so an error about a lost device can be followed by a retry.
+    pub fn read(&mut self) -> Result<String, String> {
+        if self.file.is_none() {
+            self.file = Some(self.open()?);
+        }
+        let mut buffer = [0; 256];
+        let res = self.file.as_mut().unwrap().read(&mut buffer);
+        match res {
+            // a timeout, unless the device was unplugged
+            Ok(0) if !Path::new(&self.device).exists() => {
+                self.file = None;
+                Err(format!("Lost device '{}'", self.device))
+            }
+            Ok(s_length) => Ok(String::from_utf8_lossy(&buffer[..s_length]).into_owned()),
+            Err(s_error) if s_error.kind() == ErrorKind::Interrupted => Ok(String::from("")),
+            Err(s_error) => {
+                self.file = None;
+                Err(format!("Lost device '{}' : {s_error}", self.device))
+            }
+        }
+    }
+
+    /// Open the device and set it to raw mode at the configured speed, with reads which time
+    /// out after 100 ms.
+    fn open(&self) -> Result<File, String> {
+        let file = OpenOptions::new()
+            .read(true)
+            .open(&self.device)
+            .map_err(|error| format!("Could not open device '{}' : {error}", self.device))?;
+        let stdin = file
+            .try_clone()
+            .map_err(|error| format!("Could not open device '{}' : {error}", self.device))?;
+        let output = Command::new("stty")
+            .args([
+                &self.baud.to_string(),
+                "raw",
+                "-echo",
+                "clocal",
+                "min",
+                "0",
+                "time",
+                "1",
+            ])
+            .stdin(stdin)
+            .output()
+            .map_err(|error| format!("Could not run stty : {error}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Could not set up device '{}' : {}",
+                self.device,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::{c_char, c_int, CStr};
+    use std::io::Write;
+    use std::os::fd::FromRawFd;
+
+    extern "C" {
+        fn posix_openpt(flags: c_int) -> c_int;
+        fn grantpt(fd: c_int) -> c_int;
+        fn unlockpt(fd: c_int) -> c_int;
+        fn ptsname_r(fd: c_int, buf: *mut c_char, buflen: usize) -> c_int;
+    }
+
+    /// Return the master side of a new pseudo-terminal and the path of its slave side.
+    #[cfg(target_os = "linux")]
+    fn open_pty() -> (File, String) {
+        let mut name = [0 as c_char; 64];
+        // SAFETY: plain system calls, O_RDWR | O_NOCTTY on Linux
+        unsafe {
+            let fd = posix_openpt(0o2 | 0o400);
+            assert!(fd >= 0);
+            assert_eq!(grantpt(fd), 0);
+            assert_eq!(unlockpt(fd), 0);
+            assert_eq!(ptsname_r(fd, name.as_mut_ptr(), name.len()), 0);
+            (
+                File::from_raw_fd(fd),
+                CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned(),
+            )
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_read() {
+        // the master side stands in for the receiver
+        let (mut receiver, device) = open_pty();
+        let mut serial = Serial::new(&device, 9600);
+        assert_eq!(serial.read(), Ok(String::from("")));
+        receiver.write_all(b"0101\n").unwrap();
+        let mut symbols = String::from("");
+        for _ in 0..10 {
+            symbols += &serial.read().unwrap();
+            if symbols.ends_with('\n') {
+                break;
+            }
+        }
+        assert_eq!(symbols, "0101\n");
+        drop(receiver);
+        assert!(serial.read().unwrap_err().starts_with("Lost device"));
+        assert!(serial
+            .read()
+            .unwrap_err()
+            .starts_with("Could not open device"));
+    }
+
+    #[test]
+    fn test_read_missing() {
+        let mut serial = Serial::new("/nonexistent/ttyUSB0", 9600);
+        assert!(serial
+            .read()
+            .unwrap_err()
+            .starts_with("Could not open device '/nonexistent/ttyUSB0'"));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::input::pulses::{self, PulseThresholds};
+use crate::input::{gpio, timestamped};
+use crate::Station;
+
+/// The conversion of a log into bits, by input format.
+enum Conversion {
+    Bits,
+    Timestamped(timestamped::Converter),
+    Pulses(pulses::Converter),
+    /// edge events are converted into pulse widths and those into bits
+    Gpio(gpio::Converter, pulses::Converter),
+}
+
+/// A log which is read as it grows, e.g. from a file or from a receiver, and converted into a
+/// log with one line of bits per minute as consumed by `transmitters::Analyzer`. Only complete
+/// lines, or complete events of a binary GPIO dump, are converted, the rest is kept until it is
+/// complete.
+pub struct BitStream {
+    conversion: Conversion,
+    /// the incomplete line or event at the end of the log so far
+    pending: Vec<u8>,
+    /// if GPIO events are binary, None until enough of them arrived to tell
+    binary: Option<bool>,
+}
+
+impl BitStream {
+    /// Return a new stream at the start of a log in the given input format, or an error for WAV
+    /// files, which can only be converted once they are complete.
+    ///
+    /// # Arguments
+    /// * `input_format` - `bits`, `timestamped`, `pulses` or `gpio`
+    /// * `station` - the station which is logged
+    /// * `patterns` - patterns of timestamps for `timestamped`, see `timestamped::to_bit_log()`
+    /// * `century_pivot` - the first year within the century in the 1900s for `%y` in the
+    ///   patterns
+    /// * `thresholds` - the thresholds to classify the pulses for `pulses` and `gpio`
+    /// * `inverted` - if the carrier dips are represented by a low instead of a high level, for
+    ///   `gpio`
+    pub fn new(
+        input_format: &str,
+        station: Station,
+        patterns: &[String],
+        century_pivot: u8,
+        thresholds: &PulseThresholds,
+        inverted: bool,
+    ) -> Result<Self, String> {
+        let conversion = match input_format {
+            "bits" => Conversion::Bits,
+            "timestamped" => Conversion::Timestamped(timestamped::Converter::new(
+                station,
+                patterns,
+                century_pivot,
+            )),
+            "pulses" => Conversion::Pulses(pulses::Converter::new(station, thresholds)),
+            "gpio" => Conversion::Gpio(
+                gpio::Converter::new(station, inverted),
+                pulses::Converter::new(station, thresholds),
+            ),
+            _ => {
+                return Err(format!(
+                    "input format '{input_format}' cannot be converted as it grows"
+                ))
+            }
+        };
+        Ok(Self {
+            conversion,
+            pending: Vec::new(),
+            binary: None,
+        })
+    }
+
+    /// Convert the given data which was added to the log, return the bits and newlines it adds
+    /// to the log of bits.
+    ///
+    /// # Arguments
+    /// * `data` - the data added to the log
+    pub fn feed(&mut self, data: &[u8]) -> Result<String, String> {
+        if let Conversion::Bits = self.conversion {
+            // the analyzers handle incomplete minutes themselves
+            return Ok(String::from_utf8_lossy(data).into_owned());
+        }
+        self.pending.extend_from_slice(data);
+        if let (Conversion::Gpio(..), None) = (&self.conversion, self.binary) {
+            // binary events always contain NUL bytes, text never does
+            if self.pending.contains(&0) || self.pending.len() >= gpio::EVENT_SIZE {
+                self.binary = Some(self.pending.contains(&0));
+            } else {
+                return Ok(String::from(""));
+            }
+        }
+        let complete = if self.binary == Some(true) {
+            self.pending.len() - self.pending.len() % gpio::EVENT_SIZE
+        } else {
+            self.pending
+                .iter()
+                .rposition(|&byte| byte == b'\n')
+                .map_or(0, |pos| pos + 1)
+        };
+        let data: Vec<u8> = self.pending.drain(..complete).collect();
+        let text = String::from_utf8_lossy(&data);
+        match &mut self.conversion {
+            Conversion::Bits => unreachable!(),
+            Conversion::Timestamped(s_converter) => text
+                .lines()
+                .map(|line| s_converter.push_line(line))
+                .collect(),
+            Conversion::Pulses(s_converter) => text
+                .lines()
+                .map(|line| s_converter.push_line(line))
+                .collect(),
+            Conversion::Gpio(s_events, s_pulses) => s_events
+                .push_events(&data)?
+                .lines()
+                .map(|line| s_pulses.push_line(line))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed the given log to a new stream in parts of the given size, return the converted log.
+    fn feed_parts(input_format: &str, station: Station, log: &[u8], size: usize) -> String {
+        let mut stream = BitStream::new(
+            input_format,
+            station,
+            &[],
+            100,
+            &PulseThresholds::new(station),
+            false,
+        )
+        .unwrap();
+        log.chunks(size)
+            .map(|part| stream.feed(part).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_feed_bits() {
+        assert_eq!(
+            feed_parts("bits", Station::Dcf77, b"0101\n01", 3),
+            "0101\n01"
+        );
+    }
+
+    #[test]
+    fn test_feed_timestamped() {
+        let log = "1600000000.1 0\n1600000001.1 1\n1600000003.1 0\n1600000004";
+        assert_eq!(
+            feed_parts("timestamped", Station::Dcf77, log.as_bytes(), 5),
+            "01_0"
+        );
+    }
+
+    #[test]
+    fn test_feed_pulses() {
+        let log = "95 205 1850\n110 180 # comment\n250 50 1900 100";
+        assert_eq!(
+            feed_parts("pulses", Station::Dcf77, log.as_bytes(), 4),
+            "01\n01"
+        );
+    }
+
+    #[test]
+    fn test_feed_gpio() {
+        let log = "0.5 rising\n0.6 falling\n1.5 rising\n1.7 falling\n\
+                   3.5 rising\n3.6 falling\n4.5 rising\n4.7 falling\n5.5 rising";
+        assert_eq!(
+            feed_parts("gpio", Station::Dcf77, log.as_bytes(), 10),
+            "01\n0"
+        );
+    }
+
+    #[test]
+    fn test_feed_errors() {
+        let thresholds = PulseThresholds::new(Station::Msf);
+        assert!(BitStream::new("wav", Station::Msf, &[], 100, &thresholds, false).is_err());
+        let mut stream =
+            BitStream::new("pulses", Station::Msf, &[], 100, &thresholds, false).unwrap();
+        assert_eq!(stream.feed(b"100 ab"), Ok(String::from("")));
+        assert_eq!(
+            stream.feed(b"c\n"),
+            Err(String::from("line 1: invalid pulse width 'abc'"))
+        );
+    }
+}
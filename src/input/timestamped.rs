@@ -0,0 +1,481 @@
+use crate::transmitters::full_year;
+use crate::{days_from_civil, Station};
+
+/// Convert a log with one `<timestamp> <symbol>` line per second into a log with one line of
+/// bits per minute, as consumed by `dcf77::analyze_buffer()` and `msf::analyze_buffer()`.
+///
+/// Seconds without a logged symbol are filled with `_`. A new minute starts after the pulse-less
+/// second 59 for DCF77 and at the `4` (begin-of-minute) symbol for MSF, or for both after
+/// missing seconds beyond the end of the minute. An MSF minute is split after 61 seconds if its
+/// `4` is lost.
+/// Empty lines and lines starting with # are ignored.
+///
+/// # Arguments
+/// * `buffer` - the buffer containing the timestamped log
+/// * `station` - the station which was logged, this determines the valid symbols
+/// * `patterns` - patterns of timestamps to try if the timestamp is neither a number nor in ISO
+///   8601 format, see `parse_timestamp_pattern()`
+/// * `century_pivot` - the first year within the century in the 1900s for `%y` in the patterns
+pub fn to_bit_log(
+    buffer: &str,
+    station: Station,
+    patterns: &[String],
+    century_pivot: u8,
+) -> Result<String, String> {
+    let mut converter = Converter::new(station, patterns, century_pivot);
+    let mut res = String::from("");
+    for line in buffer.lines() {
+        res += &converter.push_line(line)?;
+    }
+    res += converter.finish();
+    Ok(res)
+}
+
+/// Converter of a timestamped log which is fed line by line as it grows, see `to_bit_log()`.
+pub struct Converter {
+    station: Station,
+    patterns: Vec<String>,
+    century_pivot: u8,
+    /// number of lines converted so far, for the error messages
+    lines: usize,
+    /// the time of the last symbol
+    last_time: Option<f64>,
+    /// number of symbols in the current minute
+    second: i64,
+}
+
+impl Converter {
+    /// Return a new converter, at the start of a log.
+    ///
+    /// # Arguments
+    /// * `station` - the station which was logged, this determines the valid symbols
+    /// * `patterns` - patterns of timestamps to try, see `to_bit_log()`
+    /// * `century_pivot` - the first year within the century in the 1900s for `%y` in the
+    ///   patterns
+    pub fn new(station: Station, patterns: &[String], century_pivot: u8) -> Self {
+        Self {
+            station,
+            patterns: patterns.to_vec(),
+            century_pivot,
+            lines: 0,
+            last_time: None,
+            second: 0,
+        }
+    }
+
+    /// Convert the next line of the log, return the bits and newlines it adds to the log of bits.
+    ///
+    /// # Arguments
+    /// * `line` - the next line of the timestamped log, without the newline
+    pub fn push_line(&mut self, line: &str) -> Result<String, String> {
+        let (symbols, marker): (&[char], i64) = match self.station {
+            Station::Dcf77 => (&['0', '1', '_'], 1),
+            Station::Msf => (&['0', '1', '2', '3', '4', '_'], 0),
+        };
+        self.lines += 1;
+        let mut res = String::from("");
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(res);
+        }
+        let Some((s_time, s_symbol)) = line.rsplit_once(char::is_whitespace) else {
+            return Err(format!(
+                "line {}: expected timestamp and symbol",
+                self.lines
+            ));
+        };
+        let Some(time) = parse_timestamp(s_time.trim()).or_else(|| {
+            self.patterns.iter().find_map(|pattern| {
+                parse_timestamp_pattern(s_time.trim(), pattern, self.century_pivot)
+            })
+        }) else {
+            return Err(format!(
+                "line {}: invalid timestamp '{}'",
+                self.lines,
+                s_time.trim()
+            ));
+        };
+        let symbol = match s_symbol.chars().collect::<Vec<char>>()[..] {
+            [c] if symbols.contains(&c) => c,
+            _ => return Err(format!("line {}: invalid symbol '{s_symbol}'", self.lines)),
+        };
+        if let Some(s_last_time) = self.last_time {
+            let elapsed = (time - s_last_time).round() as i64;
+            if elapsed < 1 {
+                return Err(format!("line {}: timestamp does not increase", self.lines));
+            }
+            // position of this symbol in the current minute, 0-based
+            let mut position = self.second - 1 + elapsed;
+            let minute_length = 60.max(self.second + marker);
+            // an MSF minute ends at the next `4`, as a leap second makes it 61 seconds long,
+            // unless seconds are missing in between or the `4` is lost and it grows longer
+            if position >= minute_length
+                && (self.station == Station::Dcf77 || elapsed > 1 || position >= 61)
+            {
+                push_missing(&mut res, self.second, minute_length - marker);
+                res.push('\n');
+                // not much use in adding entire minutes of missing data
+                position = (position - minute_length) % 60;
+                self.second = 0;
+            }
+            push_missing(&mut res, self.second, position);
+            self.second = position;
+        }
+        if symbol == '4' && self.second != 0 {
+            // early begin-of-minute marker, e.g. a negative leap second
+            res.push('\n');
+            self.second = 0;
+        }
+        res.push(symbol);
+        self.second += 1;
+        self.last_time = Some(time);
+        Ok(res)
+    }
+
+    /// Return the newline which ends the last minute at the end of the log, if it is complete.
+    pub fn finish(&self) -> &'static str {
+        let marker = match self.station {
+            Station::Dcf77 => 1,
+            Station::Msf => 0,
+        };
+        if self.second + marker >= 60 {
+            "\n"
+        } else {
+            ""
+        }
+    }
+}
+
+/// Append `_` characters to the buffer for the seconds which are missing in the log.
+///
+/// # Arguments
+/// * `buffer` - the buffer to append to
+/// * `from` - the first missing second
+/// * `to` - the second after the last missing second
+fn push_missing(buffer: &mut String, from: i64, to: i64) {
+    for _ in from..to {
+        buffer.push('_');
+    }
+}
+
+/// Return the number of seconds since 1970-01-01 of the given timestamp, which is either a plain
+/// (fractional) number of seconds or an ISO 8601 date and time like 2024-03-31T01:59:58.950Z,
+/// where the seconds are optional.
+///
+/// # Arguments
+/// * `timestamp` - the timestamp to parse
+pub fn parse_timestamp(timestamp: &str) -> Option<f64> {
+    if let Ok(seconds) = timestamp.parse::<f64>() {
+        return seconds.is_finite().then_some(seconds);
+    }
+    let timestamp = timestamp.strip_suffix('Z').unwrap_or(timestamp);
+    let (date, time) = timestamp.split_once(['T', ' '])?;
+    let mut date_parts = date.splitn(3, '-');
+    let year = date_parts.next()?.parse::<u16>().ok()?;
+    let month = date_parts.next()?.parse::<u8>().ok()?;
+    let day = date_parts.next()?.parse::<u8>().ok()?;
+    let mut time_parts = time.trim().splitn(3, ':');
+    let hour = time_parts.next()?.parse::<u8>().ok()?;
+    let minute = time_parts.next()?.parse::<u8>().ok()?;
+    let second = match time_parts.next() {
+        Some(s_second) => s_second.parse::<f64>().ok()?,
+        None => 0.0,
+    };
+    to_seconds(year, month, day, hour, minute, second)
+}
+
+/// Return the number of seconds since 1970-01-01 of the given timestamp in the given pattern, in
+/// which `%Y` is the year, `%y` the year within the century, `%m` the month, `%d` the day, `%H` the hour, `%M` the
+/// minute, `%S` the (fractional) second and `%%` a literal `%`. Other characters must match
+/// literally. Missing fields default to 1970-01-01 00:00:00.
+///
+/// # Arguments
+/// * `timestamp` - the timestamp to parse
+/// * `pattern` - the pattern of the timestamp, e.g. `%d.%m.%Y %H:%M:%S`
+/// * `century_pivot` - the first year within the century in the 1900s for `%y`, see
+///   `transmitters::AnalysisSettings`
+pub fn parse_timestamp_pattern(timestamp: &str, pattern: &str, century_pivot: u8) -> Option<f64> {
+    let (mut year, mut month, mut day, mut hour, mut minute, mut second) = (1970, 1, 1, 0, 0, 0.0);
+    let mut rest = timestamp;
+    let mut pattern_chars = pattern.chars();
+    while let Some(c) = pattern_chars.next() {
+        if c != '%' {
+            rest = rest.strip_prefix(c)?;
+            continue;
+        }
+        let field = pattern_chars.next()?;
+        if field == '%' {
+            rest = rest.strip_prefix('%')?;
+            continue;
+        }
+        let max_digits = if field == 'Y' { 4 } else { 2 };
+        let mut length = rest
+            .chars()
+            .take(max_digits)
+            .take_while(char::is_ascii_digit)
+            .count();
+        if length == 0 {
+            return None;
+        }
+        if field == 'S' && rest[length..].starts_with('.') {
+            length += 1 + rest[length + 1..]
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .count();
+        }
+        let (number, tail) = rest.split_at(length);
+        rest = tail;
+        match field {
+            'Y' => year = number.parse().ok()?,
+            'y' => year = full_year(number.parse().ok()?, century_pivot),
+            'm' => month = number.parse().ok()?,
+            'd' => day = number.parse().ok()?,
+            'H' => hour = number.parse().ok()?,
+            'M' => minute = number.parse().ok()?,
+            'S' => second = number.parse().ok()?,
+            _ => return None,
+        }
+    }
+    if !rest.is_empty() {
+        return None;
+    }
+    to_seconds(year, month, day, hour, minute, second)
+}
+
+/// Return the number of seconds since 1970-01-01 of the given date and time, or None if it is
+/// invalid.
+///
+/// # Arguments
+/// * `year` - the year
+/// * `month` - the month, 1-12
+/// * `day` - the day of the month, 1-31
+/// * `hour` - the hour, 0-23
+/// * `minute` - the minute, 0-59
+/// * `second` - the (fractional) second, below 61 for leap seconds
+fn to_seconds(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: f64) -> Option<f64> {
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || !(0.0..61.0).contains(&second)
+    {
+        return None;
+    }
+    Some(
+        days_from_civil(year, month, day) as f64 * 86_400.0
+            + hour as f64 * 3600.0
+            + minute as f64 * 60.0
+            + second,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Return a timestamped log of the given bit lines, one second apart, with the given
+    /// number of seconds between the last bit of a line and the first bit of the next line.
+    fn timestamped(lines: &[&str], marker: usize) -> String {
+        let mut res = String::from("");
+        let mut time = 1_600_000_000.02;
+        for line in lines {
+            for c in line.chars() {
+                res += &format!("{time:.3} {c}\n");
+                time += 1.0;
+            }
+            time += marker as f64;
+        }
+        res
+    }
+
+    const DCF77_MINUTE_1: &str = "00000000000000000010100011011110001110001110101001100110011";
+    const DCF77_MINUTE_2: &str = "00000000000000000010110011010110001110001110101001100110011";
+    const MSF_MINUTE_1: &str = "400000000220000000010000000011101000110100011101100101133110";
+    const MSF_MINUTE_2: &str = "400000000220000000010000000011101001000000000000000003113310";
+
+    #[test]
+    fn test_to_bit_log_dcf77() {
+        assert_eq!(
+            to_bit_log(
+                &timestamped(&[DCF77_MINUTE_1, DCF77_MINUTE_2], 1),
+                Station::Dcf77,
+                &[],
+                100
+            ),
+            Ok(format!("{DCF77_MINUTE_1}\n{DCF77_MINUTE_2}\n"))
+        );
+    }
+
+    #[test]
+    fn test_to_bit_log_dcf77_missing() {
+        let mut log = timestamped(&[DCF77_MINUTE_1, DCF77_MINUTE_2], 1);
+        // remove second 10 and the last second of the first minute
+        let mut lines: Vec<&str> = log.lines().collect();
+        lines.remove(58);
+        lines.remove(10);
+        log = lines.join("\n");
+        assert_eq!(
+            to_bit_log(&log, Station::Dcf77, &[], 100),
+            Ok(format!(
+                "{}_{}_\n{DCF77_MINUTE_2}\n",
+                &DCF77_MINUTE_1[..10],
+                &DCF77_MINUTE_1[11..58]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_to_bit_log_dcf77_leap_second() {
+        let leap_minute = format!("{DCF77_MINUTE_1}0");
+        assert_eq!(
+            to_bit_log(
+                &timestamped(&[&leap_minute, DCF77_MINUTE_2], 1),
+                Station::Dcf77,
+                &[],
+                100
+            ),
+            Ok(format!("{leap_minute}\n{DCF77_MINUTE_2}\n"))
+        );
+    }
+
+    #[test]
+    fn test_to_bit_log_msf() {
+        assert_eq!(
+            to_bit_log(
+                &timestamped(&[MSF_MINUTE_1, MSF_MINUTE_2], 0),
+                Station::Msf,
+                &[],
+                100
+            ),
+            Ok(format!("{MSF_MINUTE_1}\n{MSF_MINUTE_2}\n"))
+        );
+    }
+
+    #[test]
+    fn test_to_bit_log_msf_leap_second() {
+        let leap_minute = format!("{}0{}", &MSF_MINUTE_1[..17], &MSF_MINUTE_1[17..]);
+        assert_eq!(
+            to_bit_log(
+                &timestamped(&[&leap_minute, MSF_MINUTE_2], 0),
+                Station::Msf,
+                &[],
+                100
+            ),
+            Ok(format!("{leap_minute}\n{MSF_MINUTE_2}\n"))
+        );
+    }
+
+    #[test]
+    fn test_to_bit_log_msf_missing() {
+        // the begin-of-minute marker of the second minute is missing
+        let log = timestamped(&[MSF_MINUTE_1, &MSF_MINUTE_2[1..]], 1);
+        assert_eq!(
+            to_bit_log(&log, Station::Msf, &[], 100),
+            Ok(format!("{MSF_MINUTE_1}\n_{}\n", &MSF_MINUTE_2[1..]))
+        );
+    }
+
+    #[test]
+    fn test_to_bit_log_msf_lost_marker() {
+        // the begin-of-minute marker of the second minute is garbled without a gap
+        let garbled = format!("0{}", &MSF_MINUTE_2[1..]);
+        assert_eq!(
+            to_bit_log(
+                &timestamped(&[MSF_MINUTE_1, &garbled, MSF_MINUTE_1], 0),
+                Station::Msf,
+                &[],
+                100
+            ),
+            Ok(format!(
+                "{MSF_MINUTE_1}0\n{}\n{MSF_MINUTE_1}\n",
+                &MSF_MINUTE_2[1..]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_to_bit_log_msf_short_minute() {
+        let short_minute = &MSF_MINUTE_1[..59];
+        assert_eq!(
+            to_bit_log(
+                &timestamped(&[short_minute, MSF_MINUTE_2], 0),
+                Station::Msf,
+                &[],
+                100
+            ),
+            Ok(format!("{short_minute}\n{MSF_MINUTE_2}\n"))
+        );
+    }
+
+    #[test]
+    fn test_to_bit_log_errors() {
+        assert!(to_bit_log("1600000000", Station::Dcf77, &[], 100).is_err());
+        assert!(to_bit_log("1600000000 2", Station::Dcf77, &[], 100).is_err());
+        assert!(to_bit_log("1600000000 01", Station::Msf, &[], 100).is_err());
+        assert!(to_bit_log("yesterday 0", Station::Msf, &[], 100).is_err());
+        assert!(to_bit_log("1600000001 0\n1600000000 1", Station::Msf, &[], 100).is_err());
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1600000000.5"), Some(1_600_000_000.5));
+        assert_eq!(parse_timestamp("1970-01-01T00:00:01.25Z"), Some(1.25));
+        assert_eq!(
+            parse_timestamp("2017-01-01 00:01:00"),
+            Some(1_483_228_860.0)
+        );
+        assert_eq!(parse_timestamp("2017-01-01T00:02"), Some(1_483_228_920.0));
+        assert_eq!(parse_timestamp("2017-13-01 00:00:00"), None);
+        assert_eq!(parse_timestamp("2017-01-01"), None);
+        assert_eq!(parse_timestamp("inf"), None);
+    }
+
+    #[test]
+    fn test_to_bit_log_patterns() {
+        let log = "31.03.2024 01:59:58,9 0\n31.03.2024 01:59:59,9 1\n";
+        assert!(to_bit_log(log, Station::Dcf77, &[], 100).is_err());
+        assert_eq!(
+            to_bit_log(
+                &log.replace(',', "."),
+                Station::Dcf77,
+                &[String::from("%H:%M"), String::from("%d.%m.%Y %H:%M:%S")],
+                100
+            ),
+            Ok(String::from("01"))
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp_pattern() {
+        assert_eq!(
+            parse_timestamp_pattern("01.01.2017 00:01:00", "%d.%m.%Y %H:%M:%S", 100),
+            Some(1_483_228_860.0)
+        );
+        assert_eq!(
+            parse_timestamp_pattern("20170101000102.5", "%Y%m%d%H%M%S", 100),
+            Some(1_483_228_862.5)
+        );
+        assert_eq!(
+            parse_timestamp_pattern("17/01/01 00:02 %", "%y/%m/%d %H:%M %%", 100),
+            Some(1_483_228_920.0)
+        );
+        assert_eq!(
+            parse_timestamp_pattern("95/01/01", "%y/%m/%d", 90),
+            Some(788_918_400.0)
+        );
+        assert_eq!(
+            parse_timestamp_pattern("00:00:01", "%H:%M:%S", 100),
+            Some(1.0)
+        );
+        assert_eq!(parse_timestamp_pattern("01.13.2017", "%d.%m.%Y", 100), None);
+        assert_eq!(
+            parse_timestamp_pattern("01.01.2017 x", "%d.%m.%Y", 100),
+            None
+        );
+        assert_eq!(
+            parse_timestamp_pattern("01.01.2017", "%d.%m.%Y %H", 100),
+            None
+        );
+        assert_eq!(parse_timestamp_pattern("1", "%q", 100), None);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::input::pulses;
+use crate::Station;
+
+/// Length in milliseconds of the moving average used to detect the envelope.
+const ENVELOPE_WINDOW: f32 = 10.0;
+
+/// Audio samples of a single channel, normalized to -1.0..1.0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Samples {
+    pub sample_rate: u32,
+    pub samples: Vec<f32>,
+}
+
+/// Read the first channel of a PCM or floating point WAV file.
+///
+/// # Arguments
+/// * `data` - contents of the WAV file
+pub fn read_wav(data: &[u8]) -> Result<Samples, String> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err(String::from("not a WAV file"));
+    }
+    let mut format = None;
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let chunk_id = &data[pos..pos + 4];
+        let chunk_len = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let chunk = &data[pos + 8..data.len().min(pos + 8 + chunk_len)];
+        if chunk_id == b"fmt " {
+            if chunk.len() < 16 {
+                return Err(String::from("fmt chunk too short"));
+            }
+            let mut tag = u16::from_le_bytes([chunk[0], chunk[1]]);
+            if tag == 0xfffe && chunk.len() >= 26 {
+                // WAVE_FORMAT_EXTENSIBLE, the sub format starts with the actual tag
+                tag = u16::from_le_bytes([chunk[24], chunk[25]]);
+            }
+            let channels = u16::from_le_bytes([chunk[2], chunk[3]]) as usize;
+            let sample_rate = u32::from_le_bytes(chunk[4..8].try_into().unwrap());
+            let bits = u16::from_le_bytes([chunk[14], chunk[15]]);
+            if channels == 0 || sample_rate == 0 {
+                return Err(String::from("invalid fmt chunk"));
+            }
+            if !SUPPORTED_FORMATS.contains(&(tag, bits)) {
+                return Err(format!(
+                    "unsupported sample format {tag} with {bits} bits per sample"
+                ));
+            }
+            format = Some((tag, channels, sample_rate, bits));
+        } else if chunk_id == b"data" {
+            let Some((tag, channels, sample_rate, bits)) = format else {
+                return Err(String::from("data chunk before fmt chunk"));
+            };
+            let frame_len = channels * (bits as usize).div_ceil(8);
+            let samples = chunk
+                .chunks_exact(frame_len)
+                .map(|frame| decode_sample(tag, bits, frame))
+                .collect();
+            return Ok(Samples {
+                sample_rate,
+                samples,
+            });
+        }
+        // chunks are padded to an even length
+        pos += 8 + chunk_len + chunk_len % 2;
+    }
+    Err(String::from("no data chunk found"))
+}
+
+/// The supported pairs of format tag and bits per sample, see `decode_sample()`.
+const SUPPORTED_FORMATS: [(u16, u16); 5] = [(1, 8), (1, 16), (1, 24), (1, 32), (3, 32)];
+
+/// Decode the first sample of the frame.
+///
+/// # Arguments
+/// * `tag` - format tag, 1 for integer PCM or 3 for IEEE floating point
+/// * `bits` - number of bits per sample, one of `SUPPORTED_FORMATS` with the tag
+/// * `frame` - the bytes of the frame, first channel first
+fn decode_sample(tag: u16, bits: u16, frame: &[u8]) -> f32 {
+    match (tag, bits) {
+        (1, 8) => (frame[0] as f32 - 128.0) / 128.0,
+        (1, 16) => i16::from_le_bytes([frame[0], frame[1]]) as f32 / 32_768.0,
+        (1, 24) => {
+            (i32::from_le_bytes([0, frame[0], frame[1], frame[2]]) >> 8) as f32 / 8_388_608.0
+        }
+        (1, 32) => i32::from_le_bytes(frame[0..4].try_into().unwrap()) as f32 / 2_147_483_648.0,
+        _ => f32::from_le_bytes(frame[0..4].try_into().unwrap()),
+    }
+}
+
+/// Return a log of pulse widths in milliseconds as consumed by `pulses::to_bit_log()`, obtained
+/// by detecting the dips in the envelope of the audio.
+///
+/// # Arguments
+/// * `audio` - the samples to analyze
+/// * `station` - the station which was recorded
+/// * `inverted` - if the carrier dips are represented by a high instead of a low level, as is
+///   the case for the output of some receivers
+pub fn to_pulse_log(audio: &Samples, station: Station, inverted: bool) -> String {
+    let ms = |samples: usize| samples as f64 * 1000.0 / audio.sample_rate as f64;
+    let dips: Vec<_> = find_dips(audio, inverted)
+        .into_iter()
+        .map(|(start, end)| (ms(start), ms(end)))
+        .collect();
+    pulses::from_dips(&dips, station)
+}
+
+/// Return the (start, end) sample indices of all dips in the envelope of the audio.
+///
+/// The envelope is a moving average of the rectified signal, a dip is where it is below the
+/// middle between its 5th and 95th percentile, with some hysteresis.
+///
+/// # Arguments
+/// * `audio` - the samples to analyze
+/// * `inverted` - if the dips are represented by a high level instead
+fn find_dips(audio: &Samples, inverted: bool) -> Vec<(usize, usize)> {
+    let window = ((audio.sample_rate as f32 * ENVELOPE_WINDOW / 1000.0) as usize).max(1);
+    let mut envelope = Vec::with_capacity(audio.samples.len());
+    let mut sum = 0.0;
+    for (idx, sample) in audio.samples.iter().enumerate() {
+        sum += sample.abs();
+        if idx >= window {
+            sum -= audio.samples[idx - window].abs();
+        }
+        // skip the start, where the window is not filled yet
+        if idx + 1 >= window {
+            let level = sum / window as f32;
+            envelope.push(if inverted { -level } else { level });
+        }
+    }
+    if envelope.is_empty() {
+        return Vec::new();
+    }
+    let mut sorted = envelope.clone();
+    sorted.sort_by(f32::total_cmp);
+    let low = sorted[sorted.len() / 20];
+    let high = sorted[sorted.len() * 19 / 20];
+    let threshold = (low + high) / 2.0;
+    let hysteresis = (high - low) / 10.0;
+    let mut dips = Vec::new();
+    let mut dip_start = None;
+    for (idx, &level) in envelope.iter().enumerate() {
+        match dip_start {
+            None if level < threshold - hysteresis => dip_start = Some(idx + window - 1),
+            Some(s_start) if level > threshold + hysteresis => {
+                dips.push((s_start, idx + window - 1));
+                dip_start = None;
+            }
+            _ => {}
+        }
+    }
+    dips
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::pulses::PulseThresholds;
+
+    const SAMPLE_RATE: u32 = 4000;
+
+    /// Return a 16-bit mono WAV file of a 500 Hz carrier which drops to 15% during the
+    /// given pulses of (start, width) in milliseconds.
+    fn synthesize(pulses: &[(u32, u32)], length: u32) -> Vec<u8> {
+        let mut samples = Vec::new();
+        for idx in 0..length * SAMPLE_RATE / 1000 {
+            let time = idx * 1000 / SAMPLE_RATE;
+            let dip = pulses
+                .iter()
+                .any(|&(start, width)| time >= start && time < start + width);
+            let level = if dip { 0.15 } else { 1.0 };
+            let phase = idx as f32 * 500.0 / SAMPLE_RATE as f32 * std::f32::consts::TAU;
+            samples.push((phase.sin() * level * 30_000.0) as i16);
+        }
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + samples.len() as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+        wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(samples.len() as u32 * 2).to_le_bytes());
+        for sample in samples {
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+        wav
+    }
+
+    #[test]
+    fn test_read_wav_errors() {
+        assert!(read_wav(b"RIFF").is_err());
+        assert!(read_wav(b"RIFF\x04\x00\x00\x00WAVEdata\x00\x00\x00\x00").is_err());
+        // zero and unsupported bits per sample
+        for bits in [0u16, 12] {
+            let mut wav = synthesize(&[], 10);
+            wav[34..36].copy_from_slice(&bits.to_le_bytes());
+            assert_eq!(
+                read_wav(&wav).err(),
+                Some(format!(
+                    "unsupported sample format 1 with {bits} bits per sample"
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_wav() {
+        let audio = read_wav(&synthesize(&[], 10)).unwrap();
+        assert_eq!(audio.sample_rate, SAMPLE_RATE);
+        assert_eq!(audio.samples.len(), 40);
+        assert_eq!(audio.samples[0], 0.0);
+        assert!((audio.samples[2] - 30_000.0 / 32_768.0).abs() < 0.001); // quarter period
+        assert!(audio.samples[4].abs() < 0.001); // half period
+    }
+
+    #[test]
+    fn test_dcf77_round_trip() {
+        const BITS: &str = "00000000000000000010100011011110001110001110101001100110011";
+        let mut dips = Vec::new();
+        for minute in 0..2 {
+            for (second, bit) in BITS.chars().enumerate() {
+                let width = if bit == '1' { 200 } else { 100 };
+                dips.push((minute * 60_000 + second as u32 * 1000 + 500, width));
+            }
+        }
+        let audio = read_wav(&synthesize(&dips, 120_000)).unwrap();
+        let pulse_log = to_pulse_log(&audio, Station::Dcf77, false);
+        assert_eq!(
+            pulses::to_bit_log(
+                &pulse_log,
+                Station::Dcf77,
+                &PulseThresholds::new(Station::Dcf77)
+            ),
+            Ok(format!("{BITS}\n{BITS}"))
+        );
+    }
+
+    #[test]
+    fn test_msf_round_trip() {
+        const BITS: &str = "400000000220000000010000000011101000110100011101100101133110";
+        let mut dips = Vec::new();
+        for (second, bit) in BITS.chars().enumerate() {
+            let start = second as u32 * 1000 + 500;
+            match bit {
+                '4' => dips.push((start, 500)),
+                '2' => dips.extend([(start, 100), (start + 200, 100)]),
+                '0' => dips.push((start, 100)),
+                '1' => dips.push((start, 200)),
+                _ => dips.push((start, 300)),
+            }
+        }
+        let audio = read_wav(&synthesize(&dips, 61_000)).unwrap();
+        let pulse_log = to_pulse_log(&audio, Station::Msf, false);
+        assert_eq!(
+            pulses::to_bit_log(
+                &pulse_log,
+                Station::Msf,
+                &PulseThresholds::new(Station::Msf)
+            ),
+            Ok(format!("{BITS}\n"))
+        );
+    }
+}
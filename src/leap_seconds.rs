@@ -0,0 +1,351 @@
+use crate::civil_from_days;
+use crate::transmitters::full_year;
+use radio_datetime_utils::RadioDateTimeUtils;
+
+/// Leap seconds as published by the IERS in Bulletin C, as (year, month, value).
+/// Each leap second is inserted (or removed, for a value of -1) at the end of the given UTC month.
+const IERS_LEAP_SECONDS: [(u16, u8, i8); 27] = [
+    (1972, 6, 1),
+    (1972, 12, 1),
+    (1973, 12, 1),
+    (1974, 12, 1),
+    (1975, 12, 1),
+    (1976, 12, 1),
+    (1977, 12, 1),
+    (1978, 12, 1),
+    (1979, 12, 1),
+    (1981, 6, 1),
+    (1982, 6, 1),
+    (1983, 6, 1),
+    (1985, 6, 1),
+    (1987, 12, 1),
+    (1989, 12, 1),
+    (1990, 12, 1),
+    (1992, 6, 1),
+    (1993, 6, 1),
+    (1994, 6, 1),
+    (1995, 12, 1),
+    (1997, 6, 1),
+    (1998, 12, 1),
+    (2005, 12, 1),
+    (2008, 12, 1),
+    (2012, 6, 1),
+    (2015, 6, 1),
+    (2016, 12, 1),
+];
+
+/// Number of days between the NTP epoch (1900-01-01) and the Unix epoch (1970-01-01).
+const NTP_UNIX_EPOCH_DAYS: i64 = 25_567;
+
+/// Table of scheduled leap seconds, used to validate the leap second information broadcast by
+/// the stations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeapSecondTable {
+    entries: Vec<(u16, u8, i8)>,
+}
+
+impl Default for LeapSecondTable {
+    /// Return the built-in table of IERS leap seconds.
+    fn default() -> Self {
+        Self {
+            entries: IERS_LEAP_SECONDS.to_vec(),
+        }
+    }
+}
+
+impl LeapSecondTable {
+    /// Parse a leap second table in the format of the `leap-seconds.list` file distributed by
+    /// the IERS and NIST, i.e. lines of NTP timestamp and TAI-UTC offset with # comments.
+    ///
+    /// # Arguments
+    /// * `buffer` - contents of the leap second file
+    pub fn from_leap_seconds_list(buffer: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        let mut last_offset: Option<i64> = None;
+        for (idx, line) in buffer.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (Some(Ok(ntp_seconds)), Some(Ok(offset))) = (
+                fields.next().map(str::parse::<i64>),
+                fields.next().map(str::parse::<i64>),
+            ) else {
+                return Err(format!("line {}: expected timestamp and offset", idx + 1));
+            };
+            if let Some(s_last_offset) = last_offset {
+                let value = offset - s_last_offset;
+                if value != 1 && value != -1 {
+                    return Err(format!("line {}: offset changes by {value}", idx + 1));
+                }
+                // the timestamp is the start of the month following the leap second
+                let (year, month, _) =
+                    civil_from_days(ntp_seconds.div_euclid(86_400) - NTP_UNIX_EPOCH_DAYS);
+                let (year, month) = if month == 1 {
+                    (year - 1, 12)
+                } else {
+                    (year, month - 1)
+                };
+                entries.push((year, month, value as i8));
+            }
+            last_offset = Some(offset);
+        }
+        if last_offset.is_none() {
+            return Err(String::from("no leap second entries found"));
+        }
+        Ok(Self { entries })
+    }
+
+    /// Return the value of the leap second scheduled at the end of the given UTC month, if any.
+    ///
+    /// # Arguments
+    /// * `year` - four-digit year
+    /// * `month` - month of the year, 1-12
+    pub fn get(&self, year: u16, month: u8) -> Option<i8> {
+        self.entries
+            .iter()
+            .find(|&&(e_year, e_month, _)| e_year == year && e_month == month)
+            .map(|&(_, _, value)| value)
+    }
+}
+
+/// Validates decoded minutes against a leap second table. It remembers if an upcoming leap
+/// second was announced so that a missing announcement can be reported at the insertion point.
+pub struct LeapSecondCheck {
+    table: LeapSecondTable,
+    century_pivot: u8,
+    utc_offset: i16,
+    has_announcement: bool,
+    announced: Option<(u16, u8)>,
+}
+
+impl LeapSecondCheck {
+    /// Create a new leap second validator.
+    ///
+    /// # Arguments
+    /// * `table` - table with the scheduled leap seconds
+    /// * `century_pivot` - the first year within the century in the 1900s, see
+    ///   `transmitters::AnalysisSettings`
+    /// * `utc_offset` - offset of the broadcast winter time to UTC, in hours
+    /// * `has_announcement` - if the station announces leap seconds in advance
+    pub fn new(
+        table: &LeapSecondTable,
+        century_pivot: u8,
+        utc_offset: i16,
+        has_announcement: bool,
+    ) -> Self {
+        Self {
+            table: table.clone(),
+            century_pivot,
+            utc_offset,
+            has_announcement,
+            announced: None,
+        }
+    }
+
+    /// Return a vector of any inconsistencies between the decoded minute and the leap second
+    /// table, in plain English.
+    ///
+    /// Both stations broadcast the time of the minute starting at the next minute marker, so
+    /// the minute in which a leap second is inserted decodes to 00:00 UTC of the next month.
+    ///
+    /// # Arguments
+    /// * `rdt` - structure containing the currently decoded date/time
+    /// * `minute_length` - the length of the decoded minute in seconds
+    pub fn check(&mut self, rdt: &RadioDateTimeUtils, minute_length: u8) -> Vec<String> {
+        let mut res = Vec::new();
+        // the UTC minute during which this minute was broadcast
+        let Some((year, month, day, hour, minute)) =
+            utc_minute(rdt, self.century_pivot, self.utc_offset * 60 + 1)
+        else {
+            return res;
+        };
+        let scheduled = self.table.get(year, month);
+        let announced = rdt
+            .get_leap_second()
+            .is_some_and(|s_leap| s_leap & radio_datetime_utils::LEAP_ANNOUNCED != 0);
+        if announced {
+            if scheduled.is_none() {
+                res.push(String::from("Leap second announced but none scheduled"));
+            } else {
+                self.announced = Some((year, month));
+            }
+        }
+        if let Some(s_value) = scheduled {
+            if day == days_in_month(year, month) && hour == 23 && minute == 59 {
+                let wanted_len = (60 + s_value) as u8;
+                if minute_length != wanted_len {
+                    res.push(format!(
+                        "Minute is {minute_length} seconds instead of {wanted_len} seconds long at scheduled leap second"
+                    ));
+                }
+                if self.has_announcement && self.announced != Some((year, month)) {
+                    res.push(String::from("Scheduled leap second was not announced"));
+                }
+                self.announced = None;
+            }
+        }
+        res
+    }
+}
+
+/// Return the number of days in the given month.
+///
+/// # Arguments
+/// * `year` - four-digit year
+/// * `month` - month of the year, 1-12
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Return the decoded date and time as (year, month, day, hour, minute) in UTC, shifted back by
+/// the given amount of minutes, or None if any part is unknown.
+///
+/// # Arguments
+/// * `rdt` - structure containing the currently decoded date/time
+/// * `century_pivot` - the first year within the century in the 1900s
+/// * `offset` - the amount of minutes to subtract, excluding daylight saving time
+fn utc_minute(
+    rdt: &RadioDateTimeUtils,
+    century_pivot: u8,
+    offset: i16,
+) -> Option<(u16, u8, u8, u8, u8)> {
+    let mut year = full_year(rdt.get_year()?, century_pivot);
+    let mut month = rdt.get_month()?;
+    let mut day = rdt.get_day()?;
+    let mut minutes = rdt.get_hour()? as i16 * 60 + rdt.get_minute()? as i16 - offset;
+    if rdt.get_dst()? & radio_datetime_utils::DST_SUMMER != 0 {
+        minutes -= 60;
+    }
+    if minutes < 0 {
+        minutes += 24 * 60;
+        if day > 1 {
+            day -= 1;
+        } else {
+            if month > 1 {
+                month -= 1;
+            } else {
+                month = 12;
+                year -= 1;
+            }
+            day = days_in_month(year, month);
+        }
+    }
+    Some((year, month, day, (minutes / 60) as u8, (minutes % 60) as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEAP_SECONDS_LIST: &str = "\
+#	This is a shortened version of leap-seconds.list
+#
+#$	 3676924800
+#@	 3928521600
+3550089600	35	# 1 Jul 2012
+3644697600	36	# 1 Jul 2015
+3692217600	37	# 1 Jan 2017
+";
+
+    #[test]
+    fn test_default_table() {
+        let table = LeapSecondTable::default();
+        assert_eq!(table.get(2016, 12), Some(1));
+        assert_eq!(table.get(2012, 6), Some(1));
+        assert_eq!(table.get(2012, 12), None);
+    }
+
+    #[test]
+    fn test_from_leap_seconds_list() {
+        let table = LeapSecondTable::from_leap_seconds_list(LEAP_SECONDS_LIST).unwrap();
+        assert_eq!(table.entries, vec![(2015, 6, 1), (2016, 12, 1)]);
+    }
+
+    #[test]
+    fn test_from_leap_seconds_list_bad() {
+        assert!(LeapSecondTable::from_leap_seconds_list("").is_err());
+        assert!(LeapSecondTable::from_leap_seconds_list("2272060800\n").is_err());
+        assert!(LeapSecondTable::from_leap_seconds_list("2272060800 10\n2287785600 12\n").is_err());
+    }
+
+    /// Return the given date and time in winter time as decoded, with or without an announced
+    /// leap second. The day of the week is not checked.
+    fn decoded(
+        year: u8,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        leap: bool,
+    ) -> RadioDateTimeUtils {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.set_year(Some(year), true, false);
+        rdt.set_month(Some(month), true, false);
+        rdt.set_weekday(Some(1), true, false);
+        rdt.set_day(Some(day), true, false);
+        rdt.set_hour(Some(hour), true, false);
+        rdt.set_minute(Some(minute), true, false);
+        rdt.set_dst(Some(false), Some(false), false);
+        rdt.set_leap_second(Some(leap), 60);
+        rdt
+    }
+
+    #[test]
+    fn test_check() {
+        let table = LeapSecondTable::default();
+        // DCF77, the minutes decode to the next minute in CET
+        let mut check = LeapSecondCheck::new(&table, 100, 1, true);
+        assert_eq!(
+            check.check(&decoded(17, 1, 1, 0, 59, true), 60),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check.check(&decoded(17, 1, 1, 1, 0, false), 61),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check.check(&decoded(18, 6, 15, 12, 30, true), 60),
+            vec![String::from("Leap second announced but none scheduled")]
+        );
+        let mut check = LeapSecondCheck::new(&table, 100, 1, true);
+        assert_eq!(
+            check.check(&decoded(17, 1, 1, 1, 0, false), 60),
+            vec![
+                String::from(
+                    "Minute is 60 seconds instead of 61 seconds long at scheduled leap second"
+                ),
+                String::from("Scheduled leap second was not announced")
+            ]
+        );
+        // MSF does not announce leap seconds, the minutes decode to the next minute in UTC
+        let mut check = LeapSecondCheck::new(&table, 100, 0, false);
+        assert_eq!(
+            check.check(&decoded(17, 1, 1, 0, 0, false), 61),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check.check(&decoded(17, 1, 1, 0, 0, false), 59),
+            vec![String::from(
+                "Minute is 59 seconds instead of 61 seconds long at scheduled leap second"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_days_in_month() {
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(2100, 2), 28);
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 6), 30);
+        assert_eq!(days_in_month(2023, 12), 31);
+    }
+}
@@ -1,6 +1,69 @@
+use crate::transmitters::{MinuteError, TIME_FIELDS};
 use radio_datetime_utils::RadioDateTimeUtils;
+use std::fmt;
+use std::str::FromStr;
 
+pub mod annotate;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod capi;
+pub mod color;
+pub mod compare;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "database")]
+pub mod database;
+pub mod encoder;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod events;
+pub mod filter;
+pub mod html;
+pub mod input;
+pub mod leap_seconds;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod metrics;
+pub mod noise;
+#[cfg(feature = "python")]
+mod python;
+pub mod refclock;
+pub mod timeline;
 pub mod transmitters;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tui;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// The time stations which can be analyzed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Station {
+    Dcf77,
+    Msf,
+}
+
+impl FromStr for Station {
+    type Err = String;
+
+    /// Parse the station name, case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dcf77" => Ok(Station::Dcf77),
+            "msf" => Ok(Station::Msf),
+            _ => Err(format!(
+                "station_name must be 'dcf77' or 'msf' but is '{}'",
+                s.to_lowercase()
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Station {
+    /// Write the station name in lower case, as accepted by `from_str()`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Station::Dcf77 => "dcf77",
+            Station::Msf => "msf",
+        })
+    }
+}
 
 /// Return a string version of the given value with leading 0, truncated to two digits or ** for None.
 ///
@@ -83,31 +146,60 @@ fn str_datetime(rdt: &RadioDateTimeUtils, weekday: String, dst: Option<u8>) -> S
     )
 }
 
-/// Return a vector of any unexpected jumps in plain English.
+/// Return a vector of any unexpected jumps.
 ///
 /// # Arguments
 /// * `rdt` - structure containing the currently decoded date/time
-fn str_jumps(rdt: &RadioDateTimeUtils) -> Vec<&str> {
-    let mut jumps = Vec::new();
-    if rdt.get_jump_year() {
-        jumps.push("Year jumped");
-    }
-    if rdt.get_jump_month() {
-        jumps.push("Month jumped");
-    }
-    if rdt.get_jump_day() {
-        jumps.push("Day-of-month jumped");
-    }
-    if rdt.get_jump_weekday() {
-        jumps.push("Day-of-week jumped");
-    }
-    if rdt.get_jump_hour() {
-        jumps.push("Hour jumped");
-    }
-    if rdt.get_jump_minute() {
-        jumps.push("Minute jumped");
-    }
-    jumps
+fn jump_errors(rdt: &RadioDateTimeUtils) -> Vec<MinuteError> {
+    let jumped = [
+        rdt.get_jump_year(),
+        rdt.get_jump_month(),
+        rdt.get_jump_day(),
+        rdt.get_jump_weekday(),
+        rdt.get_jump_hour(),
+        rdt.get_jump_minute(),
+    ];
+    TIME_FIELDS
+        .into_iter()
+        .zip(jumped)
+        .filter(|&(_, jump)| jump)
+        .map(|(field, _)| MinuteError::jump(field))
+        .collect()
+}
+
+/// Return the (year, month, day) of the given number of days since 1970-01-01.
+///
+/// # Arguments
+/// * `days` - number of days since the Unix epoch
+pub(crate) fn civil_from_days(days: i64) -> (u16, u8, u8) {
+    // see https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = (yoe + era * 400 + (month <= 2) as i64) as u16;
+    (year, month, day)
+}
+
+/// Return the number of days since 1970-01-01 of the given date.
+///
+/// # Arguments
+/// * `year` - four-digit year
+/// * `month` - month of the year, 1-12
+/// * `day` - day of the month, 1-31
+pub(crate) fn days_from_civil(year: u16, month: u8, day: u8) -> i64 {
+    // see https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let y = year as i64 - (month <= 2) as i64;
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
 }
 
 #[cfg(test)]
@@ -228,4 +320,16 @@ mod tests {
             DST_PROC_JUMP_WINTER
         );
     }
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(17_167), (2017, 1, 1));
+    }
+    #[test]
+    fn test_days_from_civil() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 2, 29), 11_016);
+        assert_eq!(days_from_civil(2017, 1, 1), 17_167);
+    }
 }
@@ -1,30 +1,1088 @@
-use radio_datetime_analyzer::transmitters;
-use std::{env, fs};
+#[cfg(feature = "config")]
+use radio_datetime_analyzer::config::{self, Config};
+#[cfg(feature = "database")]
+use radio_datetime_analyzer::database::Database;
+use radio_datetime_analyzer::encoder::{self, EncoderSettings};
+#[cfg(unix)]
+use radio_datetime_analyzer::events::{EventDetector, EventSink};
+use radio_datetime_analyzer::filter::{self, MinuteFilter};
+use radio_datetime_analyzer::input::pulses::{self, PulseThresholds};
+use radio_datetime_analyzer::input::{gpio, serial::Serial, stream::BitStream, timestamped, wav};
+use radio_datetime_analyzer::leap_seconds::LeapSecondTable;
+use radio_datetime_analyzer::metrics::{self, Metrics};
+use radio_datetime_analyzer::noise::{self, Impairments};
+#[cfg(unix)]
+use radio_datetime_analyzer::refclock::{self, shm::Shm, sock::Sock};
+use radio_datetime_analyzer::transmitters::MinuteReport;
+use radio_datetime_analyzer::transmitters::{AnalysisSettings, Grouping};
+use radio_datetime_analyzer::{annotate, color, compare};
+use radio_datetime_analyzer::{html, timeline, transmitters, tui, Station};
+use std::fs::File;
+use std::io::{IsTerminal, Read, Seek, SeekFrom};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+#[cfg(unix)]
+use std::time::UNIX_EPOCH;
+use std::time::{Duration, SystemTime};
+use std::{env, fs, thread};
+
+/// Time between checks of a followed log file for new minutes, short enough for the receive
+/// time of a minute marker to be accurate.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Time between attempts to reopen a lost serial device.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The action to perform.
+enum Command {
+    /// analyze the given log file
+    Analyze { filename: String },
+    /// analyze the given log file as it grows and export metrics of the reception
+    Follow { filename: String },
+    /// encode a log of the given number of minutes, starting at the given UTC time in seconds
+    Encode { start: i64, minutes: u32 },
+    /// print the given clean log with impairments applied
+    Impair { filename: String },
+    /// report the decode success rate of the given clean log versus the impairment level
+    Simulate { filename: String },
+    /// compare the log files of two receivers of the same station
+    Compare { filenames: [String; 2] },
+    /// store the analysis of the given log files in the database
+    #[cfg(feature = "database")]
+    Import { filenames: Vec<String> },
+    /// show the minutes in the database which match the filter
+    #[cfg(feature = "database")]
+    Query,
+    /// check the configuration files, which is done while parsing the options
+    CheckConfig,
+}
+
+/// Options given on the command line.
+struct Options {
+    command: Command,
+    station: Station,
+    input_format: String,
+    output_format: String,
+    color: bool,
+    grouping: Grouping,
+    settings: AnalysisSettings,
+    pulse_thresholds: PulseThresholds,
+    invert: bool,
+    show_timeline: bool,
+    filter: MinuteFilter,
+    encoder_settings: EncoderSettings,
+    impairments: Impairments,
+    seed: u64,
+    steps: u32,
+    listen: Option<String>,
+    textfile: Option<String>,
+    #[cfg(unix)]
+    shm: Option<u8>,
+    #[cfg(unix)]
+    sock: Option<String>,
+    baud: Option<u32>,
+    #[cfg(unix)]
+    events: Option<String>,
+    #[cfg(unix)]
+    parity_threshold: u32,
+    #[cfg(unix)]
+    gap_threshold: i64,
+    #[cfg(feature = "database")]
+    database: Option<String>,
+    #[cfg(feature = "database")]
+    error: Option<String>,
+    #[cfg(feature = "database")]
+    sql: Option<String>,
+    timestamp_patterns: Vec<String>,
+    config_files: Vec<String>,
+}
 
 fn main() {
     let mut cmd_args = env::args();
     let program_name = cmd_args.next().unwrap();
-    if cmd_args.len() != 2 {
-        eprintln!("Usage: {} station_name logfile", program_name);
+    let options = match parse_args(cmd_args) {
+        Ok(Some(s_options)) => s_options,
+        Ok(None) => {
+            eprintln!(
+                "Usage: {} [--config file.toml] [--leap-seconds leap-seconds.list] \
+                 [--input bits|timestamped|pulses|wav|gpio] [--[no-]invert] \
+                 [--timestamp-pattern pattern] [--century-pivot year] \
+                 [--pulse-width symbol=ms] [--pulse-tolerance ms] [--minute-gap ms] \
+                 [--[no-]timeline] [--format text|annotated|html|tui] \
+                 [--color always|never|auto] [--grouping fields|none|nibbles] \
+                 [--[no-]only-errors] [--only kind,...] \
+                 [--from time] [--to time] [--database file.db] station_name logfile\n       \
+                 {} [--listen address:port] [--textfile file.prom] [--shm unit] \
+                 [--sock path] [--baud n] [--events syslog|journal] \
+                 [--parity-threshold n] [--gap-threshold minutes] [options of analyze] \
+                 follow station_name logfile\n       \
+                 {} [--leap-seconds leap-seconds.list] [--dut1 n] [--call-bit] \
+                 [--third-party n,...] encode station_name start minutes\n       \
+                 {} [--seed n] [--steps n] [--bit-flip p] [--dropout p] [--burst p] \
+                 [--burst-length n] [--drop-second p] [--extra-second p] \
+                 [--missing-newline p] [--truncate p] impair|simulate station_name logfile\n       \
+                 {} [--leap-seconds leap-seconds.list] [--input bits|timestamped|pulses|wav|gpio] \
+                 compare station_name logfile1 logfile2\n       \
+                 {} --database file.db [options of analyze] import station_name logfile...\n       \
+                 {} --database file.db [--error text] [--sql query] [--only-errors] \
+                 [--only kind,...] [--from time] [--to time] query station_name\n       \
+                 {} [--config file.toml] config check\n\
+                 The station_name can be left out if the configuration file has a station.",
+                program_name,
+                program_name,
+                program_name,
+                program_name,
+                program_name,
+                program_name,
+                program_name,
+                program_name
+            );
+            return;
+        }
+        Err(s_error) => {
+            eprintln!("{s_error}");
+            return;
+        }
+    };
+    match options.command {
+        Command::Analyze { ref filename } => analyze(&options, filename),
+        Command::Follow { ref filename } => follow(&options, filename),
+        Command::Encode { start, minutes } => print!(
+            "{}",
+            encoder::encode_log(
+                options.station,
+                start,
+                minutes,
+                &options.encoder_settings,
+                &options.settings.leap_seconds
+            )
+        ),
+        Command::Impair { ref filename } | Command::Simulate { ref filename } => {
+            let buffer = match fs::read_to_string(filename) {
+                Ok(s_buffer) => s_buffer,
+                Err(s_error) => {
+                    eprintln!("Could not read file '{filename}' : {s_error}");
+                    return;
+                }
+            };
+            if matches!(options.command, Command::Impair { .. }) {
+                print!(
+                    "{}",
+                    noise::impair(&buffer, options.station, &options.impairments, options.seed)
+                );
+            } else {
+                for r in noise::str_success_rates(
+                    &buffer,
+                    options.station,
+                    &options.impairments,
+                    options.steps,
+                    options.seed,
+                    &options.settings,
+                ) {
+                    println!("{r}");
+                }
+            }
+        }
+        Command::Compare { ref filenames } => {
+            let mut minutes = Vec::new();
+            for filename in filenames {
+                let Some((_, s_bits)) = read_bit_log(&options, filename) else {
+                    return;
+                };
+                minutes.push(transmitters::analyze_minutes(
+                    options.station,
+                    &s_bits,
+                    &options.settings,
+                ));
+            }
+            for r in compare::compare_minutes(options.station, &minutes[0], &minutes[1]) {
+                println!("{r}");
+            }
+        }
+        #[cfg(feature = "database")]
+        Command::Import { ref filenames } => import(&options, filenames),
+        #[cfg(feature = "database")]
+        Command::Query => query(&options),
+        Command::CheckConfig => {
+            if options.config_files.is_empty() {
+                println!("No configuration file found");
+            }
+            for filename in &options.config_files {
+                println!("{filename}: OK");
+            }
+        }
+    }
+}
+
+/// Analyze the given log file and print the results.
+///
+/// # Arguments
+/// * `options` - the options given on the command line
+/// * `filename` - the name of the log file
+fn analyze(options: &Options, filename: &str) {
+    let Some((buffer, bits)) = read_bit_log(options, filename) else {
         return;
+    };
+    let all_minutes = transmitters::analyze_minutes(options.station, &bits, &options.settings);
+    #[cfg(feature = "database")]
+    if options.database.is_some() {
+        let Some(mut database) = open_database(options) else {
+            return;
+        };
+        let res = match database.is_imported(options.station, filename) {
+            Ok(false) => database.import(options.station, filename, &all_minutes),
+            res => res.map(|_| ()),
+        };
+        if let Err(s_error) = res {
+            eprintln!("{s_error}");
+            return;
+        }
+    }
+    let minutes: Vec<_> = all_minutes
+        .iter()
+        .filter(|report| options.filter.matches(report))
+        .cloned()
+        .collect();
+    if options.output_format == "html" {
+        print!("{}", html::render(options.station, &minutes, filename));
+        return;
+    }
+    if options.output_format == "tui" {
+        if let Err(s_error) = tui::run(options.station, &minutes) {
+            eprintln!("{s_error}");
+        }
+        return;
+    }
+    for report in minutes {
+        print_report(options, report);
+    }
+    if options.show_timeline {
+        println!("Timeline:");
+        for r in timeline::str_timeline(&timeline::timeline(&all_minutes)) {
+            println!("{r}");
+        }
+    }
+    if ["pulses", "wav", "gpio"].contains(&options.input_format.as_str()) {
+        println!("Pulse width distribution:");
+        for r in pulses::str_width_distribution(&buffer, 10) {
+            println!("{r}");
+        }
+    }
+}
+
+/// Open the database given on the command line, return None after showing an error.
+///
+/// # Arguments
+/// * `options` - the options given on the command line
+#[cfg(feature = "database")]
+fn open_database(options: &Options) -> Option<Database> {
+    match Database::open(options.database.as_deref()?) {
+        Ok(s_database) => Some(s_database),
+        Err(s_error) => {
+            eprintln!("{s_error}");
+            None
+        }
     }
-    let station_name = cmd_args.next().unwrap().to_lowercase();
-    if station_name != "dcf77" && station_name != "msf" {
-        eprintln!("station_name must be 'dcf77' or 'msf' but is '{station_name}'");
+}
+
+/// Store the analysis of the given log files in the database, skipping the files which did not
+/// change since they were imported for the station.
+///
+/// # Arguments
+/// * `options` - the options given on the command line
+/// * `filenames` - the names of the log files
+#[cfg(feature = "database")]
+fn import(options: &Options, filenames: &[String]) {
+    let Some(mut database) = open_database(options) else {
         return;
+    };
+    for filename in filenames {
+        match database.is_imported(options.station, filename) {
+            Ok(true) => {
+                println!("Skipped '{filename}', it is already imported");
+                continue;
+            }
+            Ok(false) => {}
+            Err(s_error) => {
+                eprintln!("{s_error}");
+                return;
+            }
+        }
+        let Some((_, bits)) = read_bit_log(options, filename) else {
+            return;
+        };
+        let reports = transmitters::analyze_minutes(options.station, &bits, &options.settings);
+        if let Err(s_error) = database.import(options.station, filename, &reports) {
+            eprintln!("{s_error}");
+            return;
+        }
+        println!("Imported {} minutes from '{filename}'", reports.len());
     }
-    let filename = cmd_args.next().unwrap();
-    let buffer = fs::read_to_string(&filename);
-    if let Err(ref s_error) = buffer {
-        eprintln!("Could not read file '{}' : {s_error}", &filename);
+}
+
+/// Print the minutes in the database which match the filter given on the command line, or the
+/// result of the given SQL query.
+///
+/// # Arguments
+/// * `options` - the options given on the command line
+#[cfg(feature = "database")]
+fn query(options: &Options) {
+    let Some(database) = open_database(options) else {
         return;
+    };
+    let res = match &options.sql {
+        Some(s_sql) => database.query_sql(s_sql),
+        None => database.query(options.station, &options.filter, options.error.as_deref()),
+    };
+    match res {
+        Ok(s_lines) => {
+            for r in s_lines {
+                println!("{r}");
+            }
+        }
+        Err(s_error) => eprintln!("{s_error}"),
     }
-    let res = match station_name.as_str() {
-        "dcf77" => transmitters::dcf77::analyze_buffer(&buffer.unwrap()),
-        "msf" => transmitters::msf::analyze_buffer(&buffer.unwrap()),
-        _ => Vec::new(),
+}
+
+/// Print the output lines of the given minute in the output format given on the command line.
+///
+/// # Arguments
+/// * `options` - the options given on the command line
+/// * `report` - the analysis of the minute
+fn print_report(options: &Options, mut report: MinuteReport) {
+    if let Some(s_bits_line) = report.bits_line() {
+        if options.grouping != Grouping::Fields {
+            report.lines[s_bits_line] = transmitters::layout(options.station)
+                .str_grouped(&report.bits, options.grouping)
+                + "\n";
+        }
+    }
+    let lines = if options.output_format == "annotated" {
+        annotate::str_annotated(options.station, &report)
+    } else if options.color {
+        color::colorize(options.station, &report)
+    } else {
+        report.lines
     };
-    for r in res {
+    for r in lines {
         print!("{r}");
     }
 }
+
+/// Analyze the given log file as it grows, like `tail -f`, or the symbols from the given serial
+/// device as they arrive, print each new minute and export metrics of the reception over HTTP
+/// and/or to a file for the textfile collector.
+///
+/// # Arguments
+/// * `options` - the options given on the command line
+/// * `filename` - the name of the log file or of the serial device
+fn follow(options: &Options, filename: &str) {
+    let mut metrics = Metrics::new(options.station);
+    let shared = Arc::new(Mutex::new(metrics.render()));
+    if let Some(s_address) = &options.listen {
+        let listener = match TcpListener::bind(s_address) {
+            Ok(s_listener) => s_listener,
+            Err(s_error) => {
+                eprintln!("Could not listen on '{s_address}' : {s_error}");
+                return;
+            }
+        };
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || metrics::serve(&listener, &shared));
+    }
+    #[cfg(unix)]
+    let mut shm = match options.shm.map(Shm::open) {
+        Some(Ok(s_shm)) => Some(s_shm),
+        Some(Err(s_error)) => {
+            eprintln!("{s_error}");
+            return;
+        }
+        None => None,
+    };
+    #[cfg(unix)]
+    let sock = match options.sock.as_deref().map(Sock::new) {
+        Some(Ok(s_sock)) => Some(s_sock),
+        Some(Err(s_error)) => {
+            eprintln!("{s_error}");
+            return;
+        }
+        None => None,
+    };
+    #[cfg(unix)]
+    let sink = match options.events.as_deref() {
+        Some("syslog") => match EventSink::syslog("/dev/log") {
+            Ok(s_sink) => Some(s_sink),
+            Err(s_error) => {
+                eprintln!("{s_error}");
+                return;
+            }
+        },
+        Some(_) => Some(EventSink::Journal),
+        None => None,
+    };
+    #[cfg(unix)]
+    let mut detector = EventDetector::new(
+        options.station,
+        options.parity_threshold,
+        options.gap_threshold,
+    );
+    let mut serial = options.baud.map(|baud| Serial::new(filename, baud));
+    let new_stream = || {
+        BitStream::new(
+            &options.input_format,
+            options.station,
+            &options.timestamp_patterns,
+            options.settings.century_pivot,
+            &options.pulse_thresholds,
+            options.invert,
+        )
+    };
+    let mut stream = match new_stream() {
+        Ok(s_stream) => s_stream,
+        Err(s_error) => {
+            eprintln!("{s_error}");
+            return;
+        }
+    };
+    let mut analyzer = transmitters::Analyzer::new(options.station, &options.settings);
+    // the last complete minute handled, for the reference clock, and the size of the file read
+    // so far
+    #[cfg(unix)]
+    let mut previous: Option<MinuteReport> = None;
+    let mut offset = 0;
+    loop {
+        // the time of the last change of the log is only used for the reference clock
+        #[cfg_attr(not(unix), allow(unused_variables))]
+        let (data, modified) = if let Some(s_serial) = &mut serial {
+            match s_serial.read() {
+                Ok(s_symbols) if s_symbols.is_empty() => continue,
+                Ok(s_symbols) => (s_symbols.into_bytes(), Ok(SystemTime::now())),
+                Err(s_error) => {
+                    eprintln!("{s_error}");
+                    thread::sleep(RECONNECT_INTERVAL);
+                    continue;
+                }
+            }
+        } else {
+            let metadata = match fs::metadata(filename) {
+                Ok(s_metadata) => s_metadata,
+                Err(s_error) => {
+                    eprintln!("Could not read file '{filename}' : {s_error}");
+                    return;
+                }
+            };
+            if metadata.len() == offset {
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+            if metadata.len() < offset {
+                // the file was truncated or replaced, start over but keep counting
+                stream = match new_stream() {
+                    Ok(s_stream) => s_stream,
+                    Err(s_error) => {
+                        eprintln!("{s_error}");
+                        return;
+                    }
+                };
+                analyzer = transmitters::Analyzer::new(options.station, &options.settings);
+                #[cfg(unix)]
+                {
+                    previous = None;
+                }
+                offset = 0;
+            }
+            let data = match read_from(filename, offset) {
+                Ok(s_data) => s_data,
+                Err(s_error) => {
+                    eprintln!("Could not read file '{filename}' : {s_error}");
+                    return;
+                }
+            };
+            offset += data.len() as u64;
+            (data, metadata.modified())
+        };
+        let bits = match stream.feed(&data) {
+            Ok(s_bits) => s_bits,
+            Err(s_error) => {
+                eprintln!("Could not parse file '{filename}' : {s_error}");
+                return;
+            }
+        };
+        let reports = analyzer.feed(&bits);
+        for report in &reports {
+            metrics.update(report);
+            // with an event sink, only the events are logged
+            #[cfg(unix)]
+            if let Some(s_sink) = &sink {
+                for event in detector.update(report) {
+                    if let Err(s_error) = s_sink.emit(options.station, &event) {
+                        eprintln!("{s_error}");
+                    }
+                }
+                continue;
+            }
+            if options.filter.matches(report) {
+                print_report(options, report.clone());
+            }
+        }
+        #[cfg(unix)]
+        {
+            // if nothing was logged after the last minute marker, the log was last modified
+            // when it was received
+            let received = modified
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+            let sample = match (received, reports.last()) {
+                (Some(s_received), Some(s_last)) if bits.ends_with('\n') => refclock::sample(
+                    options.station,
+                    s_last,
+                    reports
+                        .len()
+                        .checked_sub(2)
+                        .map(|idx| &reports[idx])
+                        .or(previous.as_ref()),
+                    s_received,
+                    &options.settings.leap_seconds,
+                ),
+                _ => None,
+            };
+            if let Some(s_sample) = sample {
+                if let Some(s_shm) = &mut shm {
+                    s_shm.write(&s_sample);
+                }
+                if let Some(s_sock) = &sock {
+                    // chrony may not be running yet, try again with the next minute
+                    if let Err(s_error) = s_sock.send(&s_sample) {
+                        eprintln!("{s_error}");
+                    }
+                }
+            }
+            if let Some(s_last) = reports.into_iter().last() {
+                previous = Some(s_last);
+            }
+        }
+        let text = metrics.render();
+        if let Some(s_textfile) = &options.textfile {
+            if let Err(s_error) = metrics::write_textfile(s_textfile, &text) {
+                eprintln!("{s_error}");
+                return;
+            }
+        }
+        *shared.lock().unwrap() = text;
+    }
+}
+
+/// Return the contents of the given file from the given offset to its end.
+///
+/// # Arguments
+/// * `filename` - the name of the file
+/// * `offset` - the offset in bytes to start reading at
+fn read_from(filename: &str, offset: u64) -> Result<Vec<u8>, std::io::Error> {
+    let mut file = File::open(filename)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Read the given log file and convert it to a log with one line of bits per minute, return
+/// the contents of the file as text and the converted log, or None after showing an error.
+///
+/// # Arguments
+/// * `options` - the options given on the command line
+/// * `filename` - the name of the log file
+fn read_bit_log(options: &Options, filename: &str) -> Option<(String, String)> {
+    let data = match fs::read(filename) {
+        Ok(s_data) => s_data,
+        Err(s_error) => {
+            eprintln!("Could not read file '{filename}' : {s_error}");
+            return None;
+        }
+    };
+    to_bit_log(options, filename, &data)
+}
+
+/// Convert the given log to a log with one line of bits per minute, return the log as text and
+/// the converted log, or None after showing an error.
+///
+/// # Arguments
+/// * `options` - the options given on the command line
+/// * `filename` - the name of the log file or device, for the error messages
+/// * `data` - the contents of the log
+fn to_bit_log(options: &Options, filename: &str, data: &[u8]) -> Option<(String, String)> {
+    let buffer = match options.input_format.as_str() {
+        "wav" => match wav::read_wav(data) {
+            Ok(s_audio) => wav::to_pulse_log(&s_audio, options.station, options.invert),
+            Err(s_error) => {
+                eprintln!("Could not read file '{filename}' : {s_error}");
+                return None;
+            }
+        },
+        "gpio" => match gpio::read_events(data) {
+            Ok(s_edges) => gpio::to_pulse_log(&s_edges, options.station, options.invert),
+            Err(s_error) => {
+                eprintln!("Could not read file '{filename}' : {s_error}");
+                return None;
+            }
+        },
+        _ => String::from_utf8_lossy(data).into_owned(),
+    };
+    let bits = match options.input_format.as_str() {
+        "timestamped" => timestamped::to_bit_log(
+            &buffer,
+            options.station,
+            &options.timestamp_patterns,
+            options.settings.century_pivot,
+        ),
+        "pulses" | "wav" | "gpio" => {
+            pulses::to_bit_log(&buffer, options.station, &options.pulse_thresholds)
+        }
+        _ => Ok(buffer.clone()),
+    };
+    match bits {
+        Ok(s_bits) => Some((buffer, s_bits)),
+        Err(s_error) => {
+            eprintln!("Could not parse file '{filename}' : {s_error}");
+            None
+        }
+    }
+}
+
+/// Parse the command line arguments, return None if the usage should be shown.
+///
+/// # Arguments
+/// * `cmd_args` - the command line arguments, without the program name
+fn parse_args(cmd_args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let cmd_args: Vec<String> = cmd_args.collect();
+    #[cfg(feature = "config")]
+    let (paths, cmd_args) = config_paths(cmd_args)?;
+    #[cfg(not(feature = "config"))]
+    let paths: Vec<PathBuf> = Vec::new();
+    #[cfg(feature = "config")]
+    let (args, station) = read_config_files(&paths, cmd_args)?;
+    #[cfg(not(feature = "config"))]
+    let (args, station) = (cmd_args, None);
+    let mut options = parse_options(args, station)?;
+    if let Some(ref mut s_options) = options {
+        s_options.config_files = paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+    }
+    Ok(options)
+}
+
+/// Return the paths of the system-wide and per-user configuration files which exist and of the
+/// one given with --config, in that order, and the command line arguments without --config.
+///
+/// # Arguments
+/// * `cmd_args` - the command line arguments, without the program name
+#[cfg(feature = "config")]
+fn config_paths(mut cmd_args: Vec<String>) -> Result<(Vec<PathBuf>, Vec<String>), String> {
+    let mut paths: Vec<PathBuf> = config::default_paths()
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect();
+    if let Some(s_pos) = cmd_args
+        .iter()
+        .position(|arg| arg == "--config" || arg.starts_with("--config="))
+    {
+        let arg = cmd_args.remove(s_pos);
+        let path = match arg.strip_prefix("--config=") {
+            Some(s_path) => String::from(s_path),
+            None if s_pos < cmd_args.len() => cmd_args.remove(s_pos),
+            None => return Err(String::from("--config needs a value")),
+        };
+        paths.push(PathBuf::from(path));
+    }
+    Ok((paths, cmd_args))
+}
+
+/// Return the options of the given configuration files followed by the command line arguments,
+/// and the station of the configuration files.
+///
+/// The options of the files come first, in the given order, so later files override earlier
+/// ones and the command line overrides all of them. An option which is given again replaces all
+/// its values of the earlier files, also for repeated options like --timestamp-pattern.
+///
+/// # Arguments
+/// * `paths` - the paths of the configuration files, see `config_paths()`
+/// * `cmd_args` - the command line arguments, without the program name and --config
+#[cfg(feature = "config")]
+fn read_config_files(
+    paths: &[PathBuf],
+    cmd_args: Vec<String>,
+) -> Result<(Vec<String>, Option<String>), String> {
+    let mut args = Vec::new();
+    let mut station = None;
+    for path in paths {
+        let filename = path.display();
+        let text = fs::read_to_string(path)
+            .map_err(|s_error| format!("Could not read file '{filename}' : {s_error}"))?;
+        let config = Config::parse(&text)
+            .and_then(|s_config| {
+                // check the values of each file on their own, to name the file of an invalid one
+                let mut check_args = s_config.args.clone();
+                check_args.push(s_config.station.clone().unwrap_or(String::from("dcf77")));
+                check_args.push(String::from("-"));
+                parse_options(check_args, None)?;
+                Ok(s_config)
+            })
+            .map_err(|s_error| format!("Invalid configuration file '{filename}' : {s_error}"))?;
+        station = config.station.or(station);
+        config::remove_overridden(&mut args, &config.args);
+        args.extend(config.args);
+    }
+    config::remove_overridden(&mut args, &cmd_args);
+    args.extend(cmd_args);
+    Ok((args, station))
+}
+
+/// Parse the options and command, return None if the usage should be shown.
+///
+/// # Arguments
+/// * `args` - the options and command
+/// * `default_station` - the station to use if none is given
+fn parse_options(
+    args: Vec<String>,
+    default_station: Option<String>,
+) -> Result<Option<Options>, String> {
+    let mut cmd_args = args.into_iter();
+    let mut input_format = String::from("bits");
+    let mut output_format = String::from("text");
+    let mut color = std::io::stdout().is_terminal();
+    let mut grouping = Grouping::default();
+    let mut leap_seconds = LeapSecondTable::default();
+    let mut pulse_widths = Vec::new();
+    let mut pulse_tolerance = None;
+    let mut minute_gap = None;
+    let mut invert = false;
+    let mut show_timeline = false;
+    let mut filter = MinuteFilter::default();
+    let mut encoder_settings = EncoderSettings::default();
+    let mut impairments = Impairments {
+        burst_length: 10,
+        ..Impairments::default()
+    };
+    let mut seed = 1;
+    let mut steps = 10;
+    let mut listen = None;
+    let mut textfile = None;
+    #[cfg(unix)]
+    let (mut shm, mut sock, mut events) = (None, None, None);
+    let mut baud = None;
+    #[cfg(unix)]
+    let (mut parity_threshold, mut gap_threshold) = (3, 10);
+    #[cfg(feature = "database")]
+    let (mut database, mut error, mut sql) = (None, None, None);
+    let mut timestamp_patterns = Vec::new();
+    let mut century_pivot = 100;
+    let mut positional = Vec::new();
+    while let Some(arg) = cmd_args.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg);
+            continue;
+        }
+        // the flags which can be set in a configuration file can be turned off with --no-
+        if arg == "--invert" || arg == "--no-invert" {
+            invert = arg == "--invert";
+            continue;
+        }
+        if arg == "--timeline" || arg == "--no-timeline" {
+            show_timeline = arg == "--timeline";
+            continue;
+        }
+        if arg == "--only-errors" || arg == "--no-only-errors" {
+            filter.only_errors = arg == "--only-errors";
+            continue;
+        }
+        if arg == "--call-bit" {
+            encoder_settings.call_bit = true;
+            continue;
+        }
+        // options are either --name value or --name=value
+        let (name, value) = match arg.split_once('=') {
+            Some((s_name, s_value)) => (String::from(s_name), Some(String::from(s_value))),
+            None => (arg, None),
+        };
+        let Some(value) = value.or_else(|| cmd_args.next()) else {
+            return Err(format!("{name} needs a value"));
+        };
+        match name.as_str() {
+            "--leap-seconds" => {
+                let leap_buffer = fs::read_to_string(&value)
+                    .map_err(|s_error| format!("Could not read file '{value}' : {s_error}"))?;
+                leap_seconds = LeapSecondTable::from_leap_seconds_list(&leap_buffer)
+                    .map_err(|s_error| format!("Invalid leap second file '{value}' : {s_error}"))?;
+            }
+            "--input" => {
+                if !["bits", "timestamped", "pulses", "wav", "gpio"].contains(&value.as_str()) {
+                    return Err(format!(
+                        "--input must be 'bits', 'timestamped', 'pulses', 'wav' or 'gpio' but is \
+                         '{value}'"
+                    ));
+                }
+                input_format = value;
+            }
+            "--format" => {
+                if !["text", "annotated", "html", "tui"].contains(&value.as_str()) {
+                    return Err(format!(
+                        "--format must be 'text', 'annotated', 'html' or 'tui' but is '{value}'"
+                    ));
+                }
+                output_format = value;
+            }
+            "--grouping" => grouping = value.parse::<Grouping>()?,
+            "--color" => {
+                color = match value.as_str() {
+                    "always" => true,
+                    "never" => false,
+                    "auto" => std::io::stdout().is_terminal(),
+                    _ => {
+                        return Err(format!(
+                            "--color must be 'always', 'never' or 'auto' but is '{value}'"
+                        ))
+                    }
+                }
+            }
+            "--only" => {
+                filter.only =
+                    filter::parse_kinds(&value).map_err(|s_error| format!("--only {s_error}"))?
+            }
+            "--from" | "--to" => {
+                let Some(time) = filter::parse_time(&value) else {
+                    return Err(format!(
+                        "{name} must be a date and time like 2024-03-31 01:59 but is '{value}'"
+                    ));
+                };
+                if name == "--from" {
+                    filter.from = Some(time);
+                } else {
+                    filter.to = Some(time);
+                }
+            }
+            "--pulse-width" => {
+                let Some((symbol, width)) = value.split_once('=') else {
+                    return Err(format!("--pulse-width must be symbol=ms but is '{value}'"));
+                };
+                let mut symbol_chars = symbol.chars();
+                let (Some(s_symbol), None) = (symbol_chars.next(), symbol_chars.next()) else {
+                    return Err(format!(
+                        "--pulse-width needs a single symbol but got '{symbol}'"
+                    ));
+                };
+                pulse_widths.push((s_symbol, parse_number(&name, width)?));
+            }
+            "--pulse-tolerance" => pulse_tolerance = Some(parse_number(&name, &value)?),
+            "--minute-gap" => minute_gap = Some(parse_number(&name, &value)?),
+            "--dut1" => {
+                encoder_settings.dut1 = parse_number(&name, &value)?;
+                if !(-8..=8).contains(&encoder_settings.dut1) {
+                    return Err(format!("--dut1 must be in -8..=8 but is '{value}'"));
+                }
+            }
+            "--third-party" => {
+                encoder_settings.third_party = value
+                    .split(',')
+                    .map(|payload| match payload.strip_prefix("0x") {
+                        Some(s_hex) => u16::from_str_radix(s_hex, 16)
+                            .map_err(|_| format!("{name} must be a number but is '{payload}'")),
+                        None => parse_number(&name, payload),
+                    })
+                    .collect::<Result<Vec<u16>, String>>()?;
+            }
+            "--listen" => listen = Some(value),
+            "--textfile" => textfile = Some(value),
+            #[cfg(unix)]
+            "--shm" => shm = Some(parse_number(&name, &value)?),
+            #[cfg(unix)]
+            "--sock" => sock = Some(value),
+            "--baud" => baud = Some(parse_number(&name, &value)?),
+            #[cfg(unix)]
+            "--events" => {
+                if !["syslog", "journal"].contains(&value.as_str()) {
+                    return Err(format!(
+                        "--events must be 'syslog' or 'journal' but is '{value}'"
+                    ));
+                }
+                events = Some(value);
+            }
+            #[cfg(unix)]
+            "--parity-threshold" => parity_threshold = parse_number(&name, &value)?,
+            #[cfg(unix)]
+            "--gap-threshold" => gap_threshold = parse_number(&name, &value)?,
+            #[cfg(feature = "database")]
+            "--database" => database = Some(value),
+            #[cfg(feature = "database")]
+            "--error" => error = Some(value),
+            #[cfg(feature = "database")]
+            "--sql" => sql = Some(value),
+            "--timestamp-pattern" => timestamp_patterns.push(value),
+            "--century-pivot" => {
+                century_pivot = parse_number(&name, &value)?;
+                if century_pivot > 100 {
+                    return Err(format!(
+                        "--century-pivot must be in 0..=100 but is '{value}'"
+                    ));
+                }
+            }
+            "--seed" => seed = parse_number(&name, &value)?,
+            "--steps" => steps = parse_number(&name, &value)?,
+            "--burst-length" => impairments.burst_length = parse_number(&name, &value)?,
+            "--bit-flip" => impairments.bit_flip = parse_rate(&name, &value)?,
+            "--dropout" => impairments.dropout = parse_rate(&name, &value)?,
+            "--burst" => impairments.burst = parse_rate(&name, &value)?,
+            "--drop-second" => impairments.drop_second = parse_rate(&name, &value)?,
+            "--extra-second" => impairments.extra_second = parse_rate(&name, &value)?,
+            "--missing-newline" => impairments.missing_newline = parse_rate(&name, &value)?,
+            "--truncate" => impairments.truncate = parse_rate(&name, &value)?,
+            _ => return Err(format!("Unknown option '{name}'")),
+        }
+    }
+    if baud.is_some() && input_format == "wav" {
+        return Err(String::from("--baud cannot be used with --input wav"));
+    }
+    let is_check_config = positional == ["config", "check"];
+    if let (Some(s_station), false) = (default_station, is_check_config) {
+        // the station comes after the name of the command, if any
+        let commands = [
+            "follow", "encode", "impair", "simulate", "compare", "import", "query",
+        ];
+        let index = match positional.first() {
+            Some(s_command) if commands.contains(&s_command.as_str()) => 1,
+            _ => 0,
+        };
+        if positional
+            .get(index)
+            .is_none_or(|name| name.parse::<Station>().is_err())
+        {
+            positional.insert(index, s_station);
+        }
+    }
+    let command = match positional.len() {
+        2 if is_check_config => Command::CheckConfig,
+        #[cfg(feature = "database")]
+        2 if positional[0] == "query" => Command::Query,
+        2 => Command::Analyze {
+            filename: positional[1].clone(),
+        },
+        4 if positional[0] == "encode" => {
+            let Some(start) = timestamped::parse_timestamp(&positional[2]) else {
+                return Err(format!("Invalid start time '{}'", positional[2]));
+            };
+            positional.remove(0);
+            Command::Encode {
+                start: start.floor() as i64,
+                minutes: parse_number("minutes", &positional[2])?,
+            }
+        }
+        4 if positional[0] == "compare" => Command::Compare {
+            filenames: [positional.remove(2), positional.remove(2)],
+        },
+        3 if positional[0] == "follow" => Command::Follow {
+            filename: positional.remove(2),
+        },
+        3 if positional[0] == "impair" => Command::Impair {
+            filename: positional.remove(2),
+        },
+        3 if positional[0] == "simulate" => Command::Simulate {
+            filename: positional.remove(2),
+        },
+        #[cfg(feature = "database")]
+        3.. if positional[0] == "import" => Command::Import {
+            filenames: positional.split_off(2),
+        },
+        _ => return Ok(None),
+    };
+    if !matches!(
+        command,
+        Command::Analyze { .. } | Command::Encode { .. } | Command::CheckConfig
+    ) {
+        positional.remove(0);
+    }
+    #[cfg(feature = "database")]
+    if database.is_none() && matches!(command, Command::Import { .. } | Command::Query) {
+        return Err(String::from("--database is needed for import and query"));
+    }
+    let station = match command {
+        // any station will do for checking the configuration
+        Command::CheckConfig => Station::Dcf77,
+        _ => positional[0].parse::<Station>()?,
+    };
+    let mut pulse_thresholds = PulseThresholds::new(station);
+    for (symbol, width) in pulse_widths {
+        pulse_thresholds.set_width(symbol, width);
+    }
+    if let Some(s_tolerance) = pulse_tolerance {
+        pulse_thresholds.tolerance = s_tolerance;
+    }
+    if minute_gap.is_some() {
+        pulse_thresholds.minute_gap = minute_gap;
+    }
+    Ok(Some(Options {
+        command,
+        station,
+        input_format,
+        output_format,
+        color,
+        grouping,
+        settings: AnalysisSettings {
+            leap_seconds,
+            century_pivot,
+        },
+        pulse_thresholds,
+        invert,
+        show_timeline,
+        filter,
+        encoder_settings,
+        impairments,
+        seed,
+        steps,
+        listen,
+        textfile,
+        #[cfg(unix)]
+        shm,
+        #[cfg(unix)]
+        sock,
+        baud,
+        #[cfg(unix)]
+        events,
+        #[cfg(unix)]
+        parity_threshold,
+        #[cfg(unix)]
+        gap_threshold,
+        #[cfg(feature = "database")]
+        database,
+        #[cfg(feature = "database")]
+        error,
+        #[cfg(feature = "database")]
+        sql,
+        timestamp_patterns,
+        config_files: Vec::new(),
+    }))
+}
+
+/// Parse the value of a numeric option.
+///
+/// # Arguments
+/// * `name` - the name of the option, for the error message
+/// * `value` - the value to parse
+fn parse_number<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("{name} must be a number but is '{value}'"))
+}
+
+/// Parse the value of an option which is a probability from 0.0 to 1.0.
+///
+/// # Arguments
+/// * `name` - the name of the option, for the error message
+/// * `value` - the value to parse
+fn parse_rate(name: &str, value: &str) -> Result<f64, String> {
+    let rate: f64 = parse_number(name, value)?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("{name} must be in 0.0..=1.0 but is '{value}'"));
+    }
+    Ok(rate)
+}
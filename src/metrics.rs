@@ -0,0 +1,384 @@
+use crate::transmitters::{self, MinuteReport, ERROR_KINDS, TIME_FIELDS};
+use crate::Station;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Time after which a client which does not send its request or read the response is dropped.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Counters and gauges of the reception of a station, for monitoring.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metrics {
+    station: Station,
+    /// number of minutes analyzed
+    minutes: u64,
+    /// number of minutes with the expected length, of which the date and time were decoded
+    decoded_minutes: u64,
+    /// number of minutes decoded without any problems
+    good_minutes: u64,
+    /// number of minutes with problems, per kind in the order of `ERROR_KINDS`
+    error_minutes: [u64; ERROR_KINDS.len()],
+    /// number of bad parities, per parity group in the order of the layout of the station
+    parity_failures: Vec<u64>,
+    /// number of bits which could not be determined
+    undetermined_bits: u64,
+    /// number of jumps, per field in the order of `TIME_FIELDS`
+    jumps: [u64; TIME_FIELDS.len()],
+    /// time of the last minute decoded without problems, in seconds since 1970-01-01 00:00 UTC
+    last_good: Option<i64>,
+    /// DUT1 of the last minute decoded without problems, in tenths of a second
+    dut1: Option<i8>,
+    /// state of daylight saving time of the last minute decoded without problems
+    dst: Option<u8>,
+}
+
+impl Metrics {
+    /// Return metrics for the given station without any minutes.
+    ///
+    /// # Arguments
+    /// * `station` - the station which is received
+    pub fn new(station: Station) -> Self {
+        Self {
+            station,
+            minutes: 0,
+            decoded_minutes: 0,
+            good_minutes: 0,
+            error_minutes: [0; ERROR_KINDS.len()],
+            parity_failures: vec![0; transmitters::layout(station).parities.len()],
+            undetermined_bits: 0,
+            jumps: [0; TIME_FIELDS.len()],
+            last_good: None,
+            dut1: None,
+            dst: None,
+        }
+    }
+
+    /// Add the given minute to the metrics.
+    ///
+    /// # Arguments
+    /// * `report` - the analysis of the minute
+    pub fn update(&mut self, report: &MinuteReport) {
+        self.minutes += 1;
+        if report.time.is_some() {
+            self.decoded_minutes += 1;
+        }
+        for (idx, kind) in ERROR_KINDS.into_iter().enumerate() {
+            if report.has_error(kind) {
+                self.error_minutes[idx] += 1;
+            }
+        }
+        self.undetermined_bits += report.bits.chars().filter(|&c| c == '_').count() as u64;
+        for error in &report.errors {
+            if let Some(s_failure) = error
+                .parity
+                .and_then(|idx| self.parity_failures.get_mut(idx))
+            {
+                *s_failure += 1;
+            }
+            if let Some(s_idx) = error
+                .jump
+                .and_then(|field| TIME_FIELDS.iter().position(|f| *f == field))
+            {
+                self.jumps[s_idx] += 1;
+            }
+        }
+        if let (true, Some(s_time)) = (report.is_good(), report.time) {
+            self.good_minutes += 1;
+            self.last_good = s_time.unix_time(self.station).or(self.last_good);
+            self.dut1 = report.dut1;
+            self.dst = s_time.dst;
+        }
+    }
+
+    /// Return the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let station = self.station;
+        let labels = |extra: &str| {
+            if extra.is_empty() {
+                format!("{{station=\"{station}\"}}")
+            } else {
+                format!("{{station=\"{station}\",{extra}}}")
+            }
+        };
+        let mut res = String::from("");
+        let mut family = |name: &str, kind: &str, help: &str, samples: Vec<(String, String)>| {
+            res += &format!("# HELP radio_datetime_{name} {help}\n");
+            res += &format!("# TYPE radio_datetime_{name} {kind}\n");
+            for (s_labels, s_value) in samples {
+                res += &format!("radio_datetime_{name}{s_labels} {s_value}\n");
+            }
+        };
+        family(
+            "minutes_total",
+            "counter",
+            "Minutes received.",
+            vec![(labels(""), self.minutes.to_string())],
+        );
+        family(
+            "decoded_minutes_total",
+            "counter",
+            "Minutes of the expected length, of which the date and time were decoded.",
+            vec![(labels(""), self.decoded_minutes.to_string())],
+        );
+        family(
+            "good_minutes_total",
+            "counter",
+            "Minutes decoded without any problems.",
+            vec![(labels(""), self.good_minutes.to_string())],
+        );
+        family(
+            "error_minutes_total",
+            "counter",
+            "Minutes with problems, per kind of problem.",
+            ERROR_KINDS
+                .iter()
+                .zip(self.error_minutes)
+                .map(|(kind, count)| (labels(&format!("kind=\"{kind}\"")), count.to_string()))
+                .collect(),
+        );
+        family(
+            "parity_failures_total",
+            "counter",
+            "Bad parities, per parity group.",
+            transmitters::layout(self.station)
+                .parities
+                .iter()
+                .zip(&self.parity_failures)
+                .map(|(parity, count)| {
+                    let group = parity.name.trim_end_matches(" parity").to_lowercase();
+                    (labels(&format!("group=\"{group}\"")), count.to_string())
+                })
+                .collect(),
+        );
+        family(
+            "undetermined_bits_total",
+            "counter",
+            "Bits which could not be determined.",
+            vec![(labels(""), self.undetermined_bits.to_string())],
+        );
+        family(
+            "jumps_total",
+            "counter",
+            "Unexpected jumps in the date and time, per field.",
+            TIME_FIELDS
+                .iter()
+                .zip(self.jumps)
+                .map(|(field, count)| {
+                    let field = field.to_string().to_lowercase();
+                    (labels(&format!("field=\"{field}\"")), count.to_string())
+                })
+                .collect(),
+        );
+        family(
+            "last_good_minute_timestamp_seconds",
+            "gauge",
+            "Broadcast time of the last minute decoded without problems.",
+            self.last_good
+                .map(|time| (labels(""), time.to_string()))
+                .into_iter()
+                .collect(),
+        );
+        family(
+            "dut1_seconds",
+            "gauge",
+            "DUT1 of the last minute decoded without problems.",
+            self.dut1
+                .map(|dut1| (labels(""), format!("{:.1}", dut1 as f64 / 10.0)))
+                .into_iter()
+                .collect(),
+        );
+        family(
+            "dst_summer",
+            "gauge",
+            "1 if the last minute decoded without problems is in summer time.",
+            self.dst
+                .map(|dst| {
+                    let summer = dst & radio_datetime_utils::DST_SUMMER != 0;
+                    (labels(""), (summer as u8).to_string())
+                })
+                .into_iter()
+                .collect(),
+        );
+        family(
+            "dst_announced",
+            "gauge",
+            "1 if a change of daylight saving time is announced in the last minute decoded \
+             without problems.",
+            self.dst
+                .map(|dst| {
+                    let announced = dst & radio_datetime_utils::DST_ANNOUNCED != 0;
+                    (labels(""), (announced as u8).to_string())
+                })
+                .into_iter()
+                .collect(),
+        );
+        res
+    }
+}
+
+/// Return the HTTP response to the given request, with the given metrics for `/metrics`.
+///
+/// # Arguments
+/// * `request` - the HTTP request, at least its first line
+/// * `metrics` - the metrics in the Prometheus text exposition format
+pub fn http_response(request: &str, metrics: &str) -> String {
+    let mut words = request.split_whitespace();
+    let (status, body) = match (words.next(), words.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", metrics),
+        (Some("GET"), _) => ("404 Not Found", "Not found, try /metrics\n"),
+        _ => ("405 Method Not Allowed", "Only GET is supported\n"),
+    };
+    format!(
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n\
+         {body}",
+        body.len()
+    )
+}
+
+/// Answer HTTP requests on the given listener with the current metrics, forever. The
+/// connections are answered one after the other, so a slow client holds up the others for at
+/// most `CLIENT_TIMEOUT` per read and write, without tying up a thread per connection.
+///
+/// # Arguments
+/// * `listener` - the socket to accept connections on
+/// * `metrics` - the current metrics in the Prometheus text exposition format
+pub fn serve(listener: &TcpListener, metrics: &Mutex<String>) {
+    for stream in listener.incoming().flatten() {
+        answer(stream, metrics);
+    }
+}
+
+/// Answer the HTTP request on the given connection with the current metrics, giving up if the
+/// client does not send its request or read the response in time.
+///
+/// # Arguments
+/// * `stream` - the connection to the client
+/// * `metrics` - the current metrics in the Prometheus text exposition format
+fn answer(mut stream: TcpStream, metrics: &Mutex<String>) {
+    if stream.set_read_timeout(Some(CLIENT_TIMEOUT)).is_err()
+        || stream.set_write_timeout(Some(CLIENT_TIMEOUT)).is_err()
+    {
+        return;
+    }
+    let mut request = [0; 1024];
+    let Ok(length) = stream.read(&mut request) else {
+        return;
+    };
+    let response = http_response(
+        &String::from_utf8_lossy(&request[..length]),
+        &metrics.lock().unwrap(),
+    );
+    // the scraper may have gone away, it will try again
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// Write the given metrics to the given file for the textfile collector of the node exporter.
+/// The file is replaced atomically so that the collector never reads a partial file.
+///
+/// # Arguments
+/// * `filename` - the name of the file, which should end in `.prom`
+/// * `metrics` - the metrics in the Prometheus text exposition format
+pub fn write_textfile(filename: &str, metrics: &str) -> Result<(), String> {
+    let temporary = format!("{filename}.tmp");
+    fs::write(&temporary, metrics)
+        .and_then(|_| fs::rename(&temporary, filename))
+        .map_err(|error| format!("Could not write file '{filename}' : {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::{msf, DecodedTime, ErrorKind, MinuteError, TimeField};
+
+    #[test]
+    fn test_update() {
+        let mut metrics = Metrics::new(Station::Msf);
+        metrics.update(&MinuteReport {
+            bits: String::from("4__0"),
+            errors: vec![MinuteError::new(
+                ErrorKind::Length,
+                "Minute is 3 seconds instead of 60 seconds long",
+            )],
+            ..MinuteReport::default()
+        });
+        metrics.update(&MinuteReport {
+            time: Some(DecodedTime {
+                year: Some(24),
+                month: Some(3),
+                day: Some(31),
+                hour: Some(2),
+                minute: Some(0),
+                dst: Some(0),
+                ..DecodedTime::default()
+            }),
+            errors: vec![
+                MinuteError::bad_parity(2, &msf::LAYOUT.parities[2]),
+                MinuteError::jump(TimeField::Hour),
+                MinuteError::jump(TimeField::Minute),
+            ],
+            ..MinuteReport::default()
+        });
+        metrics.update(&MinuteReport {
+            time: Some(DecodedTime {
+                year: Some(24),
+                month: Some(3),
+                day: Some(31),
+                hour: Some(2),
+                minute: Some(1),
+                dst: Some(radio_datetime_utils::DST_SUMMER),
+                ..DecodedTime::default()
+            }),
+            dut1: Some(-2),
+            ..MinuteReport::default()
+        });
+        assert_eq!(metrics.minutes, 3);
+        assert_eq!(metrics.decoded_minutes, 2);
+        assert_eq!(metrics.good_minutes, 1);
+        assert_eq!(metrics.error_minutes, [1, 0, 1, 1, 0]);
+        assert_eq!(metrics.parity_failures, vec![0, 0, 1, 0]);
+        assert_eq!(metrics.undetermined_bits, 2);
+        assert_eq!(metrics.jumps, [0, 0, 0, 0, 1, 1]);
+        // 2024-03-31 01:01 UTC
+        assert_eq!(metrics.last_good, Some(1_711_846_860));
+        assert_eq!(metrics.dut1, Some(-2));
+        assert_eq!(metrics.dst, Some(radio_datetime_utils::DST_SUMMER));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut metrics = Metrics::new(Station::Dcf77);
+        let text = metrics.render();
+        assert!(text.starts_with(
+            "# HELP radio_datetime_minutes_total Minutes received.\n\
+             # TYPE radio_datetime_minutes_total counter\n\
+             radio_datetime_minutes_total{station=\"dcf77\"} 0\n"
+        ));
+        assert!(text.contains(
+            "radio_datetime_parity_failures_total{station=\"dcf77\",group=\"date\"} 0\n"
+        ));
+        assert!(!text.contains("radio_datetime_dut1_seconds{"));
+        metrics.dut1 = Some(-2);
+        metrics.dst = Some(radio_datetime_utils::DST_SUMMER);
+        let text = metrics.render();
+        assert!(text.contains("radio_datetime_dut1_seconds{station=\"dcf77\"} -0.2\n"));
+        assert!(text.contains("radio_datetime_dst_summer{station=\"dcf77\"} 1\n"));
+        assert!(text.contains("radio_datetime_dst_announced{station=\"dcf77\"} 0\n"));
+    }
+
+    #[test]
+    fn test_http_response() {
+        let response = http_response("GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n", "a 1\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Length: 4\r\n"));
+        assert!(response.ends_with("\r\n\r\na 1\n"));
+        assert!(http_response("GET / HTTP/1.1\r\n", "").starts_with("HTTP/1.1 404"));
+        assert!(http_response("POST /metrics HTTP/1.1\r\n", "").starts_with("HTTP/1.1 405"));
+    }
+}
@@ -0,0 +1,336 @@
+use crate::transmitters::{self, AnalysisSettings};
+use crate::Station;
+
+/// Impairments to apply to a clean log, the rates are probabilities from 0.0 to 1.0.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Impairments {
+    /// probability that a bit is flipped
+    pub bit_flip: f64,
+    /// probability that a bit is lost, i.e. replaced by `_`
+    pub dropout: f64,
+    /// probability that a burst of lost bits starts at a bit
+    pub burst: f64,
+    /// number of bits lost in each burst
+    pub burst_length: u32,
+    /// probability that a second is missing from the log
+    pub drop_second: f64,
+    /// probability that a random extra second is inserted after a second
+    pub extra_second: f64,
+    /// probability that the newline at the end of a minute is missing
+    pub missing_newline: f64,
+    /// probability that a minute is truncated at a random second
+    pub truncate: f64,
+}
+
+impl Impairments {
+    /// Return a copy of these impairments with all rates multiplied by the given factor.
+    ///
+    /// # Arguments
+    /// * `factor` - the factor to multiply the rates with, the results are capped at 1.0
+    pub fn scaled(&self, factor: f64) -> Self {
+        let scale = |rate: f64| (rate * factor).clamp(0.0, 1.0);
+        Self {
+            bit_flip: scale(self.bit_flip),
+            dropout: scale(self.dropout),
+            burst: scale(self.burst),
+            burst_length: self.burst_length,
+            drop_second: scale(self.drop_second),
+            extra_second: scale(self.extra_second),
+            missing_newline: scale(self.missing_newline),
+            truncate: scale(self.truncate),
+        }
+    }
+}
+
+/// Small deterministic pseudo-random number generator (SplitMix64), so that the impairments can
+/// be reproduced from the seed.
+struct Rng(u64);
+
+impl Rng {
+    /// Return the next pseudo-random 64-bit value.
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Return true with the given probability.
+    ///
+    /// # Arguments
+    /// * `rate` - the probability from 0.0 to 1.0
+    fn chance(&mut self, rate: f64) -> bool {
+        // 53 bits fit exactly in the mantissa of an f64
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < rate
+    }
+
+    /// Return a pseudo-random index below the given bound, which must not be 0.
+    ///
+    /// # Arguments
+    /// * `bound` - the exclusive upper bound
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// Return the given log of bits with random impairments applied to it.
+///
+/// # Arguments
+/// * `buffer` - the buffer containing the clean log
+/// * `station` - the station which was logged, this determines the valid symbols
+/// * `impairments` - the impairments to apply
+/// * `seed` - seed of the pseudo-random generator, the same seed gives the same result
+pub fn impair(buffer: &str, station: Station, impairments: &Impairments, seed: u64) -> String {
+    let symbols: &[char] = match station {
+        Station::Dcf77 => &['0', '1'],
+        Station::Msf => &['0', '1', '2', '3'],
+    };
+    let mut rng = Rng(seed);
+    let mut res = String::from("");
+    let mut burst_left = 0;
+    for line in buffer.split_inclusive('\n') {
+        let mut chars: Vec<char> = line.chars().collect();
+        if rng.chance(impairments.truncate) {
+            let has_newline = chars.last() == Some(&'\n');
+            chars.truncate(rng.below(chars.len().max(1)));
+            if has_newline {
+                chars.push('\n');
+            }
+        }
+        for c in chars {
+            if c == '\n' {
+                if !rng.chance(impairments.missing_newline) {
+                    res.push(c);
+                }
+                continue;
+            }
+            if c != '_' && c != '4' && !symbols.contains(&c) {
+                // comments and other text are copied as is
+                res.push(c);
+                continue;
+            }
+            if rng.chance(impairments.drop_second) {
+                continue;
+            }
+            if burst_left == 0 && rng.chance(impairments.burst) {
+                burst_left = impairments.burst_length;
+            }
+            if burst_left > 0 {
+                burst_left -= 1;
+                res.push('_');
+            } else if rng.chance(impairments.dropout) {
+                res.push('_');
+            } else if rng.chance(impairments.bit_flip) {
+                res.push(flip(c, station, &mut rng));
+            } else {
+                res.push(c);
+            }
+            if rng.chance(impairments.extra_second) {
+                res.push(symbols[rng.below(symbols.len())]);
+            }
+        }
+    }
+    res
+}
+
+/// Return the given symbol with one bit flipped. For MSF, either the A or the B bit is flipped.
+///
+/// # Arguments
+/// * `c` - the symbol to flip, `_` and the MSF begin-of-minute marker are left as is
+/// * `station` - the station which was logged
+/// * `rng` - the pseudo-random generator to pick the MSF bit with
+fn flip(c: char, station: Station, rng: &mut Rng) -> char {
+    match (station, c) {
+        (Station::Dcf77, '0') => '1',
+        (Station::Dcf77, '1') => '0',
+        (Station::Msf, '0'..='3') => {
+            let value = c as u8 - b'0';
+            // bit 0 is the A bit, bit 1 is the B bit
+            (b'0' + (value ^ (1 << rng.below(2)))) as char
+        }
+        _ => c,
+    }
+}
+
+/// Return the number of minutes in the given log which decode without any problems.
+///
+/// # Arguments
+/// * `buffer` - the buffer containing the log
+/// * `station` - the station which was logged
+/// * `settings` - the settings of the analysis
+pub fn good_minutes(buffer: &str, station: Station, settings: &AnalysisSettings) -> usize {
+    transmitters::analyze_minutes(station, buffer, settings)
+        .iter()
+        .filter(|report| report.is_good())
+        .count()
+}
+
+/// Return the decode success rate of the given clean log for increasing levels of impairment,
+/// as text. Level 1.0 applies the given impairments, lower levels scale them down.
+///
+/// # Arguments
+/// * `buffer` - the buffer containing the clean log
+/// * `station` - the station which was logged
+/// * `impairments` - the impairments at level 1.0
+/// * `steps` - the number of steps from level 0.0 to level 1.0
+/// * `seed` - seed of the pseudo-random generator
+/// * `settings` - the settings of the analysis
+pub fn str_success_rates(
+    buffer: &str,
+    station: Station,
+    impairments: &Impairments,
+    steps: u32,
+    seed: u64,
+    settings: &AnalysisSettings,
+) -> Vec<String> {
+    let total = buffer.matches('\n').count();
+    let mut res = Vec::new();
+    for step in 0..=steps {
+        let level = step as f64 / steps.max(1) as f64;
+        let impaired = impair(buffer, station, &impairments.scaled(level), seed);
+        let good = good_minutes(&impaired, station, settings);
+        res.push(format!(
+            "level={level:.2} good={good}/{total} success={:.1}%",
+            if total == 0 {
+                0.0
+            } else {
+                good as f64 * 100.0 / total as f64
+            }
+        ));
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::{encode_log, EncoderSettings};
+
+    const DCF77_MINUTE: &str = "00000000000000000010100011011110001110001110101001100110011\n";
+    const MSF_MINUTE: &str = "400000000220000000010000000011101000110100011101100101133110\n";
+
+    #[test]
+    fn test_impair_none() {
+        let impairments = Impairments::default();
+        assert_eq!(
+            impair(DCF77_MINUTE, Station::Dcf77, &impairments, 1),
+            DCF77_MINUTE
+        );
+        assert_eq!(
+            impair(MSF_MINUTE, Station::Msf, &impairments, 1),
+            MSF_MINUTE
+        );
+    }
+
+    #[test]
+    fn test_impair_all() {
+        let dropout = Impairments {
+            dropout: 1.0,
+            ..Impairments::default()
+        };
+        assert_eq!(impair("0110\n01", Station::Dcf77, &dropout, 1), "____\n__");
+        let bit_flip = Impairments {
+            bit_flip: 1.0,
+            ..Impairments::default()
+        };
+        assert_eq!(impair("0110_\n", Station::Dcf77, &bit_flip, 1), "1001_\n");
+        let missing_newline = Impairments {
+            missing_newline: 1.0,
+            ..Impairments::default()
+        };
+        assert_eq!(
+            impair("01\n10\n", Station::Dcf77, &missing_newline, 1),
+            "0110"
+        );
+        let drop_second = Impairments {
+            drop_second: 1.0,
+            ..Impairments::default()
+        };
+        assert_eq!(impair("01\n10\n", Station::Dcf77, &drop_second, 1), "\n\n");
+    }
+
+    #[test]
+    fn test_impair_msf_bit_flip() {
+        let bit_flip = Impairments {
+            bit_flip: 1.0,
+            ..Impairments::default()
+        };
+        let impaired = impair(MSF_MINUTE, Station::Msf, &bit_flip, 7);
+        assert_eq!(impaired.len(), MSF_MINUTE.len());
+        for (old, new) in MSF_MINUTE.chars().zip(impaired.chars()) {
+            if old == '4' || old == '\n' {
+                assert_eq!(old, new);
+            } else {
+                assert_eq!((old as u8 ^ new as u8).count_ones(), 1, "{old} {new}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_impair_burst() {
+        let burst = Impairments {
+            burst: 1.0,
+            burst_length: 3,
+            ..Impairments::default()
+        };
+        assert_eq!(impair("0101\n", Station::Dcf77, &burst, 1), "____\n");
+    }
+
+    #[test]
+    fn test_impair_seed() {
+        let impairments = Impairments {
+            bit_flip: 0.1,
+            dropout: 0.1,
+            extra_second: 0.05,
+            truncate: 0.5,
+            ..Impairments::default()
+        };
+        let log = DCF77_MINUTE.repeat(10);
+        assert_eq!(
+            impair(&log, Station::Dcf77, &impairments, 42),
+            impair(&log, Station::Dcf77, &impairments, 42)
+        );
+        assert_ne!(
+            impair(&log, Station::Dcf77, &impairments, 42),
+            impair(&log, Station::Dcf77, &impairments, 43)
+        );
+    }
+
+    #[test]
+    fn test_scaled() {
+        let impairments = Impairments {
+            bit_flip: 0.4,
+            burst_length: 5,
+            ..Impairments::default()
+        };
+        let scaled = impairments.scaled(0.5);
+        assert_eq!(scaled.bit_flip, 0.2);
+        assert_eq!(scaled.burst_length, 5);
+        assert_eq!(impairments.scaled(3.0).bit_flip, 1.0);
+    }
+
+    #[test]
+    fn test_str_success_rates() {
+        let settings = AnalysisSettings::default();
+        // 2011-03-27 00:30 UTC
+        let log = encode_log(
+            Station::Dcf77,
+            1_301_185_800,
+            10,
+            &EncoderSettings::default(),
+            &settings.leap_seconds,
+        );
+        let impairments = Impairments {
+            dropout: 1.0,
+            ..Impairments::default()
+        };
+        assert_eq!(
+            str_success_rates(&log, Station::Dcf77, &impairments, 1, 1, &settings),
+            vec![
+                "level=0.00 good=10/10 success=100.0%",
+                "level=1.00 good=0/10 success=0.0%"
+            ]
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use crate::leap_seconds::LeapSecondTable;
+use crate::transmitters::{self, AnalysisSettings, MinuteReport};
+use crate::Station;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Parse the given station name and analysis settings for Python, raising ValueError if any of
+/// them is invalid.
+///
+/// # Arguments
+/// * `station` - the station name, `dcf77` or `msf`
+/// * `leap_seconds` - the contents of a `leap-seconds.list` file, None for the built-in table
+/// * `century_pivot` - the first year within the century in the 1900s, 0-100
+fn parse_station(
+    station: &str,
+    leap_seconds: Option<&str>,
+    century_pivot: u8,
+) -> PyResult<(Station, AnalysisSettings)> {
+    let station = station.parse::<Station>().map_err(PyValueError::new_err)?;
+    let leap_seconds = match leap_seconds {
+        Some(s_list) => LeapSecondTable::from_leap_seconds_list(s_list).map_err(|error| {
+            PyValueError::new_err(format!("Invalid leap second table : {error}"))
+        })?,
+        None => LeapSecondTable::default(),
+    };
+    if century_pivot > 100 {
+        return Err(PyValueError::new_err(format!(
+            "century_pivot must be in 0..=100 but is '{century_pivot}'"
+        )));
+    }
+    Ok((
+        station,
+        AnalysisSettings {
+            leap_seconds,
+            century_pivot,
+        },
+    ))
+}
+
+/// Return the given minute as a Python dict, with None for the parts which could not be decoded.
+///
+/// # Arguments
+/// * `py` - the Python interpreter
+/// * `station` - the station which was received, for the UTC time
+/// * `report` - the analysis of the minute
+fn to_dict<'py>(
+    py: Python<'py>,
+    station: Station,
+    report: &MinuteReport,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("index", report.index)?;
+    dict.set_item("bits", &report.bits)?;
+    dict.set_item("good", report.is_good())?;
+    for (name, value) in report.fields(station) {
+        dict.set_item(name, value)?;
+    }
+    dict.set_item("errors", report.error_messages())?;
+    dict.set_item("details", report.details())?;
+    Ok(dict)
+}
+
+/// Analyze a log of the given station and return a dict per minute with the decoded fields
+/// and the problems found.
+///
+/// # Arguments
+/// * `station` - the station which was logged, `dcf77` or `msf`
+/// * `buffer` - the contents of the log file
+/// * `leap_seconds` - the contents of a `leap-seconds.list` file, None for the built-in table
+/// * `century_pivot` - the year within the century from which years are in the 1900s, e.g. 90
+///   for logs from the 1990s
+#[pyfunction]
+#[pyo3(signature = (station, buffer, leap_seconds=None, century_pivot=100))]
+fn analyze_buffer<'py>(
+    py: Python<'py>,
+    station: &str,
+    buffer: &str,
+    leap_seconds: Option<&str>,
+    century_pivot: u8,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let (station, settings) = parse_station(station, leap_seconds, century_pivot)?;
+    transmitters::analyze_minutes(station, buffer, &settings)
+        .iter()
+        .map(|report| to_dict(py, station, report))
+        .collect()
+}
+
+/// An analyzer which is fed a log as it arrives, e.g. from a serial port, and returns each
+/// minute once it is complete.
+#[pyclass]
+struct Analyzer {
+    station: Station,
+    analyzer: transmitters::Analyzer,
+}
+
+#[pymethods]
+impl Analyzer {
+    #[new]
+    #[pyo3(signature = (station, leap_seconds=None, century_pivot=100))]
+    fn new(station: &str, leap_seconds: Option<&str>, century_pivot: u8) -> PyResult<Self> {
+        let (station, settings) = parse_station(station, leap_seconds, century_pivot)?;
+        Ok(Self {
+            station,
+            analyzer: transmitters::Analyzer::new(station, &settings),
+        })
+    }
+
+    /// Add the given part of the log and return a dict for each minute which was completed by
+    /// it, like `analyze_buffer()`.
+    ///
+    /// # Arguments
+    /// * `data` - the next part of the log
+    fn feed<'py>(&mut self, py: Python<'py>, data: &str) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.analyzer
+            .feed(data)
+            .iter()
+            .map(|report| to_dict(py, self.station, report))
+            .collect()
+    }
+}
+
+/// Python module of the analyzers, built with `maturin build --features python`.
+#[pymodule]
+fn radio_datetime_analyzer(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(analyze_buffer, module)?)?;
+    module.add_class::<Analyzer>()?;
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use crate::civil_from_days;
+use crate::leap_seconds::LeapSecondTable;
+use crate::transmitters::MinuteReport;
+use crate::Station;
+use std::time::Duration;
+
+#[cfg(unix)]
+pub mod shm;
+#[cfg(unix)]
+pub mod sock;
+
+/// No leap second is announced.
+pub const LEAP_NONE: u8 = 0;
+/// A leap second will be inserted at the end of the current UTC day.
+pub const LEAP_INSERT: u8 = 1;
+/// A leap second will be deleted at the end of the current UTC day.
+pub const LEAP_DELETE: u8 = 2;
+
+/// A time sample for an NTP daemon: the time of a minute marker as broadcast by the station,
+/// together with the time of the host at which it was received.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sample {
+    /// the broadcast time of the minute marker, in seconds since 1970-01-01 00:00 UTC
+    pub clock_time: i64,
+    /// the time of the host at which the minute marker was received, since 1970-01-01 00:00 UTC
+    pub receive_time: Duration,
+    /// leap second warning, one of `LEAP_NONE`, `LEAP_INSERT` and `LEAP_DELETE`
+    pub leap: u8,
+}
+
+/// Return a time sample for the given minute if it can be trusted, i.e. it and the minute before
+/// it were decoded without any problems. The jump checks compare each minute to the one before,
+/// so they only validate the minute if the minute before was good as well.
+///
+/// # Arguments
+/// * `station` - the station which was logged
+/// * `report` - the analysis of the minute, which ends at the minute marker
+/// * `previous` - the analysis of the minute before, if any
+/// * `receive_time` - the time of the host at which the minute marker was received
+/// * `leap_seconds` - table of scheduled leap seconds, for the direction of an announced leap
+///   second
+pub fn sample(
+    station: Station,
+    report: &MinuteReport,
+    previous: Option<&MinuteReport>,
+    receive_time: Duration,
+    leap_seconds: &LeapSecondTable,
+) -> Option<Sample> {
+    if !report.is_good() || !previous.is_some_and(|minute| minute.is_good()) {
+        return None;
+    }
+    let time = report.time?;
+    let clock_time = time.unix_time(station)?;
+    let announced = time
+        .leap_second
+        .is_some_and(|leap| leap & radio_datetime_utils::LEAP_ANNOUNCED != 0);
+    let leap = if announced {
+        // the minute marker starts the minute after the one in which it was broadcast
+        let (year, month, _) = civil_from_days((clock_time - 60).div_euclid(86_400));
+        if leap_seconds.get(year, month) == Some(-1) {
+            LEAP_DELETE
+        } else {
+            LEAP_INSERT
+        }
+    } else {
+        LEAP_NONE
+    };
+    Some(Sample {
+        clock_time,
+        receive_time,
+        leap,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::{DecodedTime, ErrorKind, MinuteError};
+
+    /// Return a good report for the given minute of 2017-01-01 00:xx local time.
+    fn report(minute: u8, leap_second: u8) -> MinuteReport {
+        MinuteReport {
+            time: Some(DecodedTime {
+                year: Some(17),
+                month: Some(1),
+                day: Some(1),
+                hour: Some(0),
+                minute: Some(minute),
+                dst: Some(0),
+                leap_second: Some(leap_second),
+                ..DecodedTime::default()
+            }),
+            ..MinuteReport::default()
+        }
+    }
+
+    #[test]
+    fn test_sample() {
+        let table = LeapSecondTable::default();
+        let received = Duration::from_millis(1_483_228_800_020);
+        let previous = report(59, radio_datetime_utils::LEAP_ANNOUNCED);
+        let current = report(0, radio_datetime_utils::LEAP_ANNOUNCED);
+        assert_eq!(
+            sample(Station::Dcf77, &current, Some(&previous), received, &table),
+            Some(Sample {
+                clock_time: 1_483_225_200,
+                receive_time: received,
+                leap: LEAP_INSERT,
+            })
+        );
+        assert_eq!(
+            sample(
+                Station::Msf,
+                &report(0, 0),
+                Some(&previous),
+                received,
+                &table
+            )
+            .map(|sample| (sample.clock_time, sample.leap)),
+            Some((1_483_228_800, LEAP_NONE))
+        );
+        assert_eq!(
+            sample(Station::Dcf77, &current, None, received, &table),
+            None
+        );
+        let mut bad = previous.clone();
+        bad.errors
+            .push(MinuteError::new(ErrorKind::Jump, "Minute jumped"));
+        assert_eq!(
+            sample(Station::Dcf77, &current, Some(&bad), received, &table),
+            None
+        );
+        assert_eq!(
+            sample(Station::Dcf77, &bad, Some(&previous), received, &table),
+            None
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use crate::refclock::Sample;
+use std::ffi::{c_int, c_long, c_uint, c_void};
+use std::ptr::{addr_of, addr_of_mut};
+use std::sync::atomic::{fence, Ordering};
+use std::time::Duration;
+
+/// Key of the shared memory segment of unit 0, the key of unit n is this plus n.
+const NTP_SHM_BASE: c_int = 0x4e54_5030;
+const IPC_CREAT: c_int = 0o1000;
+/// Precision of the samples as a power of two in seconds, about the jitter of the logged
+/// minute marker.
+const PRECISION: c_int = -7;
+
+extern "C" {
+    fn shmget(key: c_int, size: usize, shmflg: c_int) -> c_int;
+    fn shmat(shmid: c_int, shmaddr: *const c_void, shmflg: c_int) -> *mut c_void;
+    fn shmdt(shmaddr: *const c_void) -> c_int;
+}
+
+/// The layout of the shared memory segment, see `refclock_shm.c` of ntpd.
+#[repr(C)]
+struct ShmTime {
+    /// 0 to only use `valid`, 1 to also use `count` to detect concurrent writes
+    mode: c_int,
+    count: c_int,
+    clock_sec: c_long,
+    clock_usec: c_int,
+    receive_sec: c_long,
+    receive_usec: c_int,
+    leap: c_int,
+    precision: c_int,
+    nsamples: c_int,
+    valid: c_int,
+    clock_nsec: c_uint,
+    receive_nsec: c_uint,
+    dummy: [c_int; 8],
+}
+
+/// A shared memory segment of the NTP SHM reference clock driver, as read by ntpd (refclock
+/// type 28) and chrony (`refclock SHM`).
+pub struct Shm {
+    segment: *mut ShmTime,
+}
+
+impl Shm {
+    /// Attach to the shared memory segment of the given unit, creating it if needed. Units 0 and
+    /// 1 are only accessible to root, higher units to everyone.
+    ///
+    /// # Arguments
+    /// * `unit` - the unit of the reference clock as configured in the NTP daemon
+    pub fn open(unit: u8) -> Result<Self, String> {
+        Self::attach(
+            NTP_SHM_BASE + unit as c_int,
+            if unit < 2 { 0o600 } else { 0o666 },
+        )
+    }
+
+    /// Attach to the shared memory segment with the given key, creating it if needed.
+    ///
+    /// # Arguments
+    /// * `key` - the System V IPC key of the segment
+    /// * `permissions` - the permissions of the segment if it is created
+    fn attach(key: c_int, permissions: c_int) -> Result<Self, String> {
+        // SAFETY: plain system calls, the segment is at least as large as ShmTime
+        let segment = unsafe {
+            let id = shmget(key, size_of::<ShmTime>(), IPC_CREAT | permissions);
+            if id == -1 {
+                return Err(format!(
+                    "Could not get shared memory segment 0x{key:08x} : {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            shmat(id, std::ptr::null(), 0)
+        };
+        if segment as isize == -1 {
+            return Err(format!(
+                "Could not attach shared memory segment 0x{key:08x} : {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(Self {
+            segment: segment.cast(),
+        })
+    }
+
+    /// Write the given sample to the segment, using the `count` protocol (mode 1) so that the
+    /// NTP daemon never uses a partially written sample.
+    ///
+    /// # Arguments
+    /// * `sample` - the sample to write
+    pub fn write(&mut self, sample: &Sample) {
+        let shm = self.segment;
+        // SAFETY: the segment is attached for the lifetime of self, the fields are accessed
+        // volatile as the NTP daemon may read them concurrently
+        unsafe {
+            addr_of_mut!((*shm).valid).write_volatile(0);
+            addr_of_mut!((*shm).mode).write_volatile(1);
+            let count = addr_of!((*shm).count).read_volatile();
+            addr_of_mut!((*shm).count).write_volatile(count.wrapping_add(1));
+            fence(Ordering::SeqCst);
+            addr_of_mut!((*shm).clock_sec).write_volatile(sample.clock_time as c_long);
+            addr_of_mut!((*shm).clock_usec).write_volatile(0);
+            addr_of_mut!((*shm).clock_nsec).write_volatile(0);
+            addr_of_mut!((*shm).receive_sec)
+                .write_volatile(sample.receive_time.as_secs() as c_long);
+            addr_of_mut!((*shm).receive_usec)
+                .write_volatile(sample.receive_time.subsec_micros() as c_int);
+            addr_of_mut!((*shm).receive_nsec)
+                .write_volatile(sample.receive_time.subsec_nanos() as c_uint);
+            addr_of_mut!((*shm).leap).write_volatile(sample.leap as c_int);
+            addr_of_mut!((*shm).precision).write_volatile(PRECISION);
+            fence(Ordering::SeqCst);
+            addr_of_mut!((*shm).count).write_volatile(count.wrapping_add(2));
+            addr_of_mut!((*shm).valid).write_volatile(1);
+        }
+    }
+
+    /// Read a sample from the segment the way the NTP daemon does, return None if there is no
+    /// new sample or it was being written. The sample is marked as used.
+    pub fn read(&mut self) -> Option<Sample> {
+        let shm = self.segment;
+        // SAFETY: see write()
+        unsafe {
+            if addr_of!((*shm).valid).read_volatile() == 0 {
+                return None;
+            }
+            let count = addr_of!((*shm).count).read_volatile();
+            fence(Ordering::SeqCst);
+            // c_long is only 32 bits on some platforms
+            #[allow(clippy::unnecessary_cast)]
+            let sample = Sample {
+                clock_time: addr_of!((*shm).clock_sec).read_volatile() as i64,
+                receive_time: Duration::new(
+                    addr_of!((*shm).receive_sec).read_volatile() as u64,
+                    addr_of!((*shm).receive_nsec).read_volatile(),
+                ),
+                leap: addr_of!((*shm).leap).read_volatile() as u8,
+            };
+            fence(Ordering::SeqCst);
+            let consistent = addr_of!((*shm).count).read_volatile() == count;
+            addr_of_mut!((*shm).valid).write_volatile(0);
+            consistent.then_some(sample)
+        }
+    }
+}
+
+impl Drop for Shm {
+    fn drop(&mut self) {
+        // SAFETY: the segment was attached in attach()
+        unsafe {
+            shmdt(self.segment as *const c_void);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::refclock::LEAP_INSERT;
+
+    extern "C" {
+        fn shmctl(shmid: c_int, cmd: c_int, buf: *mut c_void) -> c_int;
+    }
+
+    #[test]
+    fn test_write_read() {
+        // a segment of this process, as the NTP units may be in use
+        let key = 0x5241_0000 | (std::process::id() & 0xffff) as c_int;
+        let mut shm = Shm::attach(key, 0o600).unwrap();
+        assert_eq!(shm.read(), None);
+        let sample = Sample {
+            clock_time: 1_483_228_800,
+            receive_time: Duration::new(1_483_228_800, 20_000_000),
+            leap: LEAP_INSERT,
+        };
+        shm.write(&sample);
+        assert_eq!(shm.read(), Some(sample));
+        assert_eq!(shm.read(), None);
+        drop(shm);
+        // SAFETY: remove the segment (IPC_RMID) which is not attached anymore
+        unsafe {
+            shmctl(shmget(key, 0, 0), 0, std::ptr::null_mut());
+        }
+    }
+}
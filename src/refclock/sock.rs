@@ -0,0 +1,120 @@
+use crate::refclock::Sample;
+use std::ffi::{c_double, c_int, c_long};
+use std::os::unix::net::UnixDatagram;
+
+/// Magic number which chrony expects in each sample, "SOCK".
+const SOCK_MAGIC: c_int = 0x534f_434b;
+
+/// The layout of a sample of the chrony SOCK reference clock driver, see `refclock_sock.c`.
+#[repr(C)]
+struct SockSample {
+    /// the time of the host at which the sample was taken, as a `struct timeval`
+    tv_sec: c_long,
+    tv_usec: c_long,
+    /// the offset of the true time to the time of the host, in seconds
+    offset: c_double,
+    /// 1 for a pulse per second sample without the seconds
+    pulse: c_int,
+    /// leap second warning, 0 for none, 1 to insert and 2 to delete one
+    leap: c_int,
+    pad: c_int,
+    magic: c_int,
+}
+
+/// A sender of samples to the socket of a chrony SOCK reference clock.
+pub struct Sock {
+    socket: UnixDatagram,
+    path: String,
+}
+
+impl Sock {
+    /// Return a sender to the given socket. The socket is created by chrony, it does not have to
+    /// exist yet.
+    ///
+    /// # Arguments
+    /// * `path` - the path of the socket as configured with `refclock SOCK` in chrony
+    pub fn new(path: &str) -> Result<Self, String> {
+        Ok(Self {
+            socket: UnixDatagram::unbound()
+                .map_err(|error| format!("Could not create socket : {error}"))?,
+            path: String::from(path),
+        })
+    }
+
+    /// Send the given sample to chrony.
+    ///
+    /// # Arguments
+    /// * `sample` - the sample to send
+    pub fn send(&self, sample: &Sample) -> Result<(), String> {
+        self.socket
+            .send_to(&encode(sample), &self.path)
+            .map(|_| ())
+            .map_err(|error| format!("Could not send sample to '{}' : {error}", self.path))
+    }
+}
+
+/// Return the given sample as sent to chrony.
+///
+/// # Arguments
+/// * `sample` - the sample to encode
+fn encode(sample: &Sample) -> Vec<u8> {
+    let sock_sample = SockSample {
+        tv_sec: sample.receive_time.as_secs() as c_long,
+        tv_usec: sample.receive_time.subsec_micros() as c_long,
+        offset: sample.clock_time as f64 - sample.receive_time.as_secs_f64(),
+        pulse: 0,
+        leap: sample.leap as c_int,
+        pad: 0,
+        magic: SOCK_MAGIC,
+    };
+    // SAFETY: SockSample is plain old data with the C layout which chrony expects
+    unsafe {
+        std::slice::from_raw_parts(
+            (&sock_sample as *const SockSample).cast::<u8>(),
+            size_of::<SockSample>(),
+        )
+    }
+    .to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::refclock::LEAP_INSERT;
+    use std::time::Duration;
+
+    #[test]
+    fn test_send() {
+        // stand-in for chrony, listening on the socket
+        let path = std::env::temp_dir().join(format!("rda-sock-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let chrony = UnixDatagram::bind(&path).unwrap();
+        let sock = Sock::new(path.to_str().unwrap()).unwrap();
+        sock.send(&Sample {
+            clock_time: 1_483_228_800,
+            receive_time: Duration::new(1_483_228_799, 750_000_000),
+            leap: LEAP_INSERT,
+        })
+        .unwrap();
+        let mut buffer = [0; 64];
+        let length = chrony.recv(&mut buffer).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(length, size_of::<SockSample>());
+        // SAFETY: the buffer holds a complete SockSample, read_unaligned() copies it out
+        let received = unsafe { buffer.as_ptr().cast::<SockSample>().read_unaligned() };
+        assert_eq!(received.tv_sec, 1_483_228_799);
+        assert_eq!(received.tv_usec, 750_000);
+        assert_eq!(received.offset, 0.25);
+        assert_eq!(received.pulse, 0);
+        assert_eq!(received.leap, 1);
+        assert_eq!(received.magic, SOCK_MAGIC);
+        assert!(Sock::new(path.to_str().unwrap())
+            .unwrap()
+            .send(&Sample {
+                clock_time: 0,
+                receive_time: Duration::ZERO,
+                leap: 0,
+            })
+            .is_err());
+    }
+}
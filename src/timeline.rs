@@ -0,0 +1,169 @@
+use crate::transmitters::{str_time, DecodedTime, MinuteReport};
+
+/// A part of the timeline of a log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimelineEntry {
+    /// contiguous minutes of good reception, with the decoded times of the first and the last one
+    Span {
+        start: DecodedTime,
+        end: DecodedTime,
+        minutes: i64,
+    },
+    /// minutes without good reception between two spans
+    Gap { minutes: i64 },
+    /// the decoded time stepped back by the given number of minutes, or repeated for 1
+    Backwards { minutes: i64 },
+}
+
+/// Return the timeline of the given minutes: the spans of good reception and what is between
+/// them. Only minutes without any problems are used, the duration of the gaps is computed from
+/// their decoded times.
+///
+/// # Arguments
+/// * `reports` - the analysis of each minute of the log
+pub fn timeline(reports: &[MinuteReport]) -> Vec<TimelineEntry> {
+    let mut res = Vec::new();
+    let mut span: Option<(DecodedTime, DecodedTime, i64)> = None;
+    let mut last_minutes = 0;
+    for report in reports.iter().filter(|report| report.is_good()) {
+        let time = report.time.unwrap();
+        let Some(minutes) = continuous_minutes(&time) else {
+            continue;
+        };
+        if let Some((s_start, s_end, s_count)) = span {
+            if minutes == last_minutes + 1 {
+                span = Some((s_start, time, s_count + 1));
+                last_minutes = minutes;
+                continue;
+            }
+            res.push(TimelineEntry::Span {
+                start: s_start,
+                end: s_end,
+                minutes: s_count,
+            });
+            res.push(if minutes > last_minutes {
+                TimelineEntry::Gap {
+                    minutes: minutes - last_minutes - 1,
+                }
+            } else {
+                TimelineEntry::Backwards {
+                    minutes: last_minutes - minutes + 1,
+                }
+            });
+        }
+        span = Some((time, time, 1));
+        last_minutes = minutes;
+    }
+    if let Some((s_start, s_end, s_count)) = span {
+        res.push(TimelineEntry::Span {
+            start: s_start,
+            end: s_end,
+            minutes: s_count,
+        });
+    }
+    res
+}
+
+/// Return the minutes of the given time with daylight saving time removed, so that they are
+/// continuous across a change of daylight saving time.
+///
+/// # Arguments
+/// * `time` - the decoded date and time
+fn continuous_minutes(time: &DecodedTime) -> Option<i64> {
+    let summer = time
+        .dst
+        .is_some_and(|dst| dst & radio_datetime_utils::DST_SUMMER != 0);
+    Some(time.minutes()? - if summer { 60 } else { 0 })
+}
+
+/// Return the given timeline in plain English.
+///
+/// # Arguments
+/// * `entries` - the timeline to stringify
+pub fn str_timeline(entries: &[TimelineEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .map(|entry| match entry {
+            TimelineEntry::Span {
+                start,
+                end,
+                minutes,
+            } => format!("{} - {}: {minutes} minutes", str_time(start), str_time(end)),
+            TimelineEntry::Gap { minutes } => format!("Gap of {minutes} minutes"),
+            TimelineEntry::Backwards { minutes } => format!("Time went back {minutes} minutes"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::{ErrorKind, MinuteError};
+
+    /// Return a good report for the given hour and minute on 2024-03-31.
+    fn report(hour: u8, minute: u8, summer: bool) -> MinuteReport {
+        MinuteReport {
+            time: Some(DecodedTime {
+                year: Some(24),
+                month: Some(3),
+                day: Some(31),
+                hour: Some(hour),
+                minute: Some(minute),
+                dst: Some(if summer {
+                    radio_datetime_utils::DST_SUMMER
+                } else {
+                    0
+                }),
+                ..DecodedTime::default()
+            }),
+            ..MinuteReport::default()
+        }
+    }
+
+    #[test]
+    fn test_timeline_empty() {
+        assert_eq!(timeline(&[]), vec![]);
+    }
+
+    #[test]
+    fn test_timeline() {
+        let mut bad = report(1, 2, false);
+        bad.errors
+            .push(MinuteError::new(ErrorKind::Parity, "Minute parity bad"));
+        let reports = vec![
+            report(1, 0, false),
+            report(1, 1, false),
+            bad,
+            MinuteReport::default(),
+            report(1, 5, false),
+            report(1, 3, false),
+            report(1, 4, false),
+            report(1, 4, false),
+        ];
+        assert_eq!(
+            str_timeline(&timeline(&reports)),
+            vec![
+                "24-03-31 01:00 - 24-03-31 01:01: 2 minutes",
+                "Gap of 3 minutes",
+                "24-03-31 01:05 - 24-03-31 01:05: 1 minutes",
+                "Time went back 3 minutes",
+                "24-03-31 01:03 - 24-03-31 01:04: 2 minutes",
+                "Time went back 1 minutes",
+                "24-03-31 01:04 - 24-03-31 01:04: 1 minutes",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timeline_dst() {
+        let reports = vec![report(1, 59, false), report(3, 0, true)];
+        assert_eq!(
+            timeline(&reports),
+            vec![TimelineEntry::Span {
+                start: reports[0].time.unwrap(),
+                end: reports[1].time.unwrap(),
+                minutes: 2
+            }]
+        );
+    }
+}
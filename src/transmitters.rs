@@ -1,8 +1,672 @@
+use crate::leap_seconds::LeapSecondTable;
+use crate::{days_from_civil, str_u8_02, Station};
+use radio_datetime_utils::RadioDateTimeUtils;
+use std::fmt;
+use std::str::FromStr;
+
 pub mod dcf77;
 pub mod msf;
 
+/// The settings of an analysis which do not follow from the log itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnalysisSettings {
+    /// table of scheduled leap seconds to validate the leap second information
+    pub leap_seconds: LeapSecondTable,
+    /// the year within the century from which decoded years are taken to be in the 1900s
+    /// instead of the 2000s, e.g. 90 for logs from the 1990s, 0-100
+    pub century_pivot: u8,
+}
+
+impl Default for AnalysisSettings {
+    /// Return the built-in table of leap seconds with all years in the 2000s.
+    fn default() -> Self {
+        Self {
+            leap_seconds: LeapSecondTable::default(),
+            century_pivot: 100,
+        }
+    }
+}
+
+/// Return the full year of the given year within the century.
+///
+/// # Arguments
+/// * `year` - year within the century, 0-99
+/// * `century_pivot` - the first year within the century in the 1900s, see `AnalysisSettings`
+pub(crate) fn full_year(year: u8, century_pivot: u8) -> u16 {
+    if year < century_pivot {
+        2000 + year as u16
+    } else {
+        1900 + year as u16
+    }
+}
+
+/// The decoded date and time of a minute, None for parts which could not be decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodedTime {
+    /// year within the century, 0-99
+    pub year: Option<u8>,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+    /// day of the week, as broadcast by the station
+    pub weekday: Option<u8>,
+    pub hour: Option<u8>,
+    pub minute: Option<u8>,
+    /// state of daylight saving time, see radio_datetime_utils::set_dst()
+    pub dst: Option<u8>,
+    /// state of the leap second, see radio_datetime_utils::set_leap_second()
+    pub leap_second: Option<u8>,
+    /// the first year within the century in the 1900s, see `AnalysisSettings`
+    pub century_pivot: u8,
+}
+
+impl Default for DecodedTime {
+    /// Return a date and time without any decoded parts, with all years in the 2000s.
+    fn default() -> Self {
+        Self {
+            year: None,
+            month: None,
+            day: None,
+            weekday: None,
+            hour: None,
+            minute: None,
+            dst: None,
+            leap_second: None,
+            century_pivot: 100,
+        }
+    }
+}
+
+impl DecodedTime {
+    /// Return the decoded parts of the given date and time.
+    ///
+    /// # Arguments
+    /// * `rdt` - structure containing the currently decoded date/time
+    /// * `century_pivot` - the first year within the century in the 1900s, see
+    ///   `AnalysisSettings`
+    pub fn new(rdt: &RadioDateTimeUtils, century_pivot: u8) -> Self {
+        Self {
+            year: rdt.get_year(),
+            month: rdt.get_month(),
+            day: rdt.get_day(),
+            weekday: rdt.get_weekday(),
+            hour: rdt.get_hour(),
+            minute: rdt.get_minute(),
+            dst: rdt.get_dst(),
+            leap_second: rdt.get_leap_second(),
+            century_pivot,
+        }
+    }
+
+    /// Return the number of minutes since 2000-01-01 00:00 of this date and time in the local
+    /// time of the station, or None if it is not completely decoded. This is negative for years
+    /// in the 1900s, see `century_pivot`.
+    pub fn minutes(&self) -> Option<i64> {
+        let (Some(year), Some(month), Some(day), Some(hour), Some(minute)) =
+            (self.year, self.month, self.day, self.hour, self.minute)
+        else {
+            return None;
+        };
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+            return None;
+        }
+        Some(
+            (days_from_civil(full_year(year, self.century_pivot), month, day)
+                - days_from_civil(2000, 1, 1))
+                * 1440
+                + hour as i64 * 60
+                + minute as i64,
+        )
+    }
+
+    /// Return the number of seconds since 1970-01-01 00:00 UTC of this date and time, or None if
+    /// it or the state of daylight saving time is not completely decoded.
+    ///
+    /// # Arguments
+    /// * `station` - the station which broadcast this date and time, for its time zone
+    pub fn unix_time(&self, station: Station) -> Option<i64> {
+        let summer = self.dst? & radio_datetime_utils::DST_SUMMER != 0;
+        let utc_offset = match station {
+            Station::Dcf77 => 60,
+            Station::Msf => 0,
+        } + if summer { 60 } else { 0 };
+        Some((self.minutes()? - utc_offset) * 60 + 946_684_800)
+    }
+}
+
+/// Return the given date and time as YY-MM-DD HH:MM, with ** for undecoded parts.
+///
+/// # Arguments
+/// * `time` - the date and time to stringify
+pub fn str_time(time: &DecodedTime) -> String {
+    format!(
+        "{}-{}-{} {}:{}",
+        str_u8_02(time.year),
+        str_u8_02(time.month),
+        str_u8_02(time.day),
+        str_u8_02(time.hour),
+        str_u8_02(time.minute)
+    )
+}
+
+/// The analysis of one minute of a log file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MinuteReport {
+    /// 0-based index of the minute (line) in the log
+    pub index: usize,
+    /// the bits of the minute as logged, without the newline
+    pub bits: String,
+    /// the decoded date and time, if the minute had the expected length
+    pub time: Option<DecodedTime>,
+    /// the decoded DUT1 in tenths of a second, MSF only
+    pub dut1: Option<i8>,
+    /// the output lines for this minute, each ending with a newline
+    pub lines: Vec<String>,
+    /// the problems found in this minute
+    pub errors: Vec<MinuteError>,
+}
+
+impl MinuteReport {
+    /// Add a line of output for this minute.
+    ///
+    /// # Arguments
+    /// * `line` - the line to add, without the newline
+    /// * `kind` - the kind of problem if the line describes a problem with this minute
+    fn push(&mut self, line: &str, kind: Option<ErrorKind>) {
+        match kind {
+            Some(s_kind) => self.push_error(MinuteError::new(s_kind, line)),
+            None => self.lines.push(format!("{line}\n")),
+        }
+    }
+
+    /// Add the given problem of this minute, along with a line of output for it.
+    ///
+    /// # Arguments
+    /// * `error` - the problem to add
+    fn push_error(&mut self, error: MinuteError) {
+        self.lines.push(format!("{}\n", error.message));
+        self.errors.push(MinuteError {
+            line: Some(self.lines.len() - 1),
+            ..error
+        });
+    }
+
+    /// Return the index of the output line with the bits of this minute, if it is shown.
+    pub fn bits_line(&self) -> Option<usize> {
+        // MSF minutes without the expected length only have problems
+        (0..self.lines.len()).find(|&idx| !self.is_blank_or_problem(idx))
+    }
+
+    /// Return the decoded information of this minute, which are the output lines after the bits
+    /// without the problems.
+    pub fn details(&self) -> Vec<&str> {
+        let Some(s_bits_line) = self.bits_line() else {
+            return Vec::new();
+        };
+        (s_bits_line + 1..self.lines.len())
+            .filter(|&idx| !self.is_blank_or_problem(idx))
+            .map(|idx| self.lines[idx].trim_end())
+            .collect()
+    }
+
+    /// Return if the given output line is empty or describes a problem.
+    ///
+    /// # Arguments
+    /// * `idx` - the index of the output line to check
+    fn is_blank_or_problem(&self, idx: usize) -> bool {
+        self.lines[idx].trim_end().is_empty()
+            || self.errors.iter().any(|error| error.line == Some(idx))
+    }
+
+    /// Return if this minute was decoded without any problems.
+    pub fn is_good(&self) -> bool {
+        self.time.is_some() && self.errors.is_empty()
+    }
+
+    /// Return the decoded fields of this minute by name, in the order in which the bindings
+    /// export them, with None for the parts which could not be decoded.
+    ///
+    /// # Arguments
+    /// * `station` - the station which was received, for the UTC time
+    pub fn fields(&self, station: Station) -> [(&'static str, Option<i64>); 10] {
+        let time = self.time.unwrap_or_default();
+        let field = |value: Option<u8>| value.map(i64::from);
+        [
+            ("year", field(time.year)),
+            ("month", field(time.month)),
+            ("day", field(time.day)),
+            ("weekday", field(time.weekday)),
+            ("hour", field(time.hour)),
+            ("minute", field(time.minute)),
+            ("dst", field(time.dst)),
+            ("leap_second", field(time.leap_second)),
+            ("dut1", self.dut1.map(i64::from)),
+            ("utc", time.unix_time(station)),
+        ]
+    }
+
+    /// Return the problems found in this minute in plain English.
+    pub fn error_messages(&self) -> Vec<&str> {
+        self.errors
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect()
+    }
+
+    /// Return if this minute has a problem of the given kind.
+    ///
+    /// # Arguments
+    /// * `kind` - the kind of problem to look for
+    pub fn has_error(&self, kind: ErrorKind) -> bool {
+        self.errors.iter().any(|error| error.kind == kind)
+    }
+}
+
+/// A problem found in a minute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MinuteError {
+    pub kind: ErrorKind,
+    /// the problem in plain English, as shown in the output
+    pub message: String,
+    /// the index of the failed parity check in the `parities` of the layout of the station, for
+    /// a bad parity
+    pub parity: Option<usize>,
+    /// the field of the date and time which jumped, for a jump
+    pub jump: Option<TimeField>,
+    /// the index of the output line of the minute which shows this problem, if any
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for MinuteError {
+    /// Write the problem in plain English.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl MinuteError {
+    /// Return a problem of the given kind.
+    ///
+    /// # Arguments
+    /// * `kind` - the kind of problem
+    /// * `message` - the problem in plain English
+    pub fn new(kind: ErrorKind, message: &str) -> Self {
+        Self {
+            kind,
+            message: String::from(message),
+            parity: None,
+            jump: None,
+            line: None,
+        }
+    }
+
+    /// Return a bad parity.
+    ///
+    /// # Arguments
+    /// * `index` - the index of the parity check in the `parities` of the layout of the station
+    /// * `parity` - the parity check itself
+    pub fn bad_parity(index: usize, parity: &ParityGroup) -> Self {
+        Self {
+            parity: Some(index),
+            ..Self::new(ErrorKind::Parity, &format!("{} bad", parity.name))
+        }
+    }
+
+    /// Return an unexpected jump of the given field.
+    ///
+    /// # Arguments
+    /// * `field` - the field of the date and time which jumped
+    pub fn jump(field: TimeField) -> Self {
+        Self {
+            jump: Some(field),
+            ..Self::new(ErrorKind::Jump, &format!("{field} jumped"))
+        }
+    }
+}
+
+/// The fields of the date and time which are checked for unexpected jumps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeField {
+    Year,
+    Month,
+    Day,
+    Weekday,
+    Hour,
+    Minute,
+}
+
+impl fmt::Display for TimeField {
+    /// Write the name of the field, as used in the output.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            TimeField::Year => "Year",
+            TimeField::Month => "Month",
+            TimeField::Day => "Day-of-month",
+            TimeField::Weekday => "Day-of-week",
+            TimeField::Hour => "Hour",
+            TimeField::Minute => "Minute",
+        })
+    }
+}
+
+/// The fields of the date and time which are checked for unexpected jumps, in the order in which
+/// they are listed.
+pub const TIME_FIELDS: [TimeField; 6] = [
+    TimeField::Year,
+    TimeField::Month,
+    TimeField::Day,
+    TimeField::Weekday,
+    TimeField::Hour,
+    TimeField::Minute,
+];
+
+/// The kinds of problems which can be found in a minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// a minute of the wrong length
+    Length,
+    /// wrong or undetermined fixed bits
+    Bits,
+    /// bad or undetermined parities
+    Parity,
+    /// unexpected jumps in the date and time
+    Jump,
+    /// problems with a leap second
+    Leap,
+}
+
+impl fmt::Display for ErrorKind {
+    /// Write the name of the kind, as accepted by `from_str()` and stored in the database.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::Length => "length",
+            ErrorKind::Bits => "bits",
+            ErrorKind::Parity => "parity",
+            ErrorKind::Jump => "jump",
+            ErrorKind::Leap => "leap",
+        })
+    }
+}
+
+impl FromStr for ErrorKind {
+    type Err = String;
+
+    /// Parse the name of the kind, as written by `fmt()`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ERROR_KINDS
+            .into_iter()
+            .find(|kind| kind.to_string() == s)
+            .ok_or(format!(
+                "kind must be one of {} but is '{s}'",
+                ERROR_KINDS.map(|kind| kind.to_string()).join(", ")
+            ))
+    }
+}
+
+/// The kinds of problems which can be found in a minute, in the order in which they are listed.
+pub const ERROR_KINDS: [ErrorKind; 5] = [
+    ErrorKind::Length,
+    ErrorKind::Bits,
+    ErrorKind::Parity,
+    ErrorKind::Jump,
+    ErrorKind::Leap,
+];
+
+/// The role of a field of bits within a minute, used to render it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldRole {
+    /// bits with a fixed value marking the structure of the minute
+    Marker,
+    /// announcements and other single-bit flags
+    Flag,
+    /// data which is not part of the date and time
+    Data,
+    /// BCD-encoded date and time
+    Time,
+    Parity,
+}
+
+/// A field of bits within a minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    /// first second of the field
+    pub start: usize,
+    /// second after the last second of the field
+    pub end: usize,
+    pub role: FieldRole,
+    /// the value which each bit adds to the field when it is set, empty if it is not a number
+    pub weights: &'static [i8],
+    /// the name of each bit, empty if the bits are named after the field
+    pub bit_names: &'static [&'static str],
+}
+
+impl Field {
+    /// Return the name of the given bit of this field, e.g. "Hour BCD 10" or "P2".
+    ///
+    /// # Arguments
+    /// * `bit` - the position of the bit within the field, 0-based
+    pub fn bit_name(&self, bit: usize) -> String {
+        if let Some(s_name) = self.bit_names.get(bit) {
+            String::from(*s_name)
+        } else if self.role == FieldRole::Time && bit < self.weights.len() {
+            format!("{} BCD {}", self.name, self.weights[bit])
+        } else if self.end - self.start > 1 {
+            format!("{} {}", self.name, bit + 1)
+        } else {
+            String::from(self.name)
+        }
+    }
+}
+
+/// The bits covered by a parity check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParityGroup {
+    /// name of the parity as used in the analysis, e.g. "Minute parity"
+    pub name: &'static str,
+    /// first second of the covered bits
+    pub start: usize,
+    /// second after the last second of the covered bits
+    pub end: usize,
+    /// second of the parity bit itself
+    pub parity_bit: usize,
+}
+
+/// The positions of the fields within a minute of a station, for a minute without leap second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub fields: &'static [Field],
+    pub parities: &'static [ParityGroup],
+    /// second before which a leap second is inserted or removed, None if it is added at the end
+    pub leap_second_at: Option<usize>,
+}
+
+impl Layout {
+    /// Return the position of the given second in a minute without leap second.
+    ///
+    /// # Arguments
+    /// * `second` - the second within the minute, 0-based
+    /// * `minute_length` - the length of the minute in seconds
+    pub fn nominal_second(&self, second: usize, minute_length: usize) -> usize {
+        let Some(s_leap) = self.leap_second_at else {
+            return second;
+        };
+        let offset = minute_length as isize - 60;
+        if second as isize >= s_leap as isize + offset {
+            (second as isize - offset) as usize
+        } else {
+            // an inserted leap second belongs to the field before it
+            second.min(s_leap - 1)
+        }
+    }
+
+    /// Return if a new group of bits starts at the given second, i.e. if it is preceded by a
+    /// space in the output.
+    ///
+    /// # Arguments
+    /// * `grouping` - the way to group the bits
+    /// * `second` - the second within the minute, 0-based
+    /// * `minute_length` - the length of the minute in seconds
+    pub fn starts_group(&self, grouping: Grouping, second: usize, minute_length: usize) -> bool {
+        second > 0
+            && match grouping {
+                Grouping::Fields => {
+                    let nominal = self.nominal_second(second, minute_length);
+                    self.fields.iter().any(|field| field.start == nominal)
+                }
+                Grouping::None => false,
+                Grouping::Nibbles => second.is_multiple_of(4),
+            }
+    }
+
+    /// Return the given bits of a minute grouped by spaces.
+    ///
+    /// # Arguments
+    /// * `bits` - the bits of the minute, without the newline
+    /// * `grouping` - the way to group the bits
+    pub fn str_grouped(&self, bits: &str, grouping: Grouping) -> String {
+        let minute_length = bits.chars().count();
+        let mut res = String::from("");
+        for (second, c) in bits.chars().enumerate() {
+            if self.starts_group(grouping, second, minute_length) {
+                res.push(' ');
+            }
+            res.push(c);
+        }
+        res
+    }
+
+    /// Return the field which the given second belongs to, if any.
+    ///
+    /// # Arguments
+    /// * `second` - the second within the minute, 0-based
+    /// * `minute_length` - the length of the minute in seconds
+    pub fn field_at(&self, second: usize, minute_length: usize) -> Option<&Field> {
+        let nominal = self.nominal_second(second, minute_length);
+        self.fields
+            .iter()
+            .find(|field| (field.start..field.end).contains(&nominal))
+    }
+
+    /// Return the role of the given second, which is `FieldRole::Parity` for the parity bits
+    /// also if they are part of a field with another role, or None if it is not in any field.
+    ///
+    /// # Arguments
+    /// * `second` - the second within the minute, 0-based
+    /// * `minute_length` - the length of the minute in seconds
+    pub fn role_at(&self, second: usize, minute_length: usize) -> Option<FieldRole> {
+        let nominal = self.nominal_second(second, minute_length);
+        if self
+            .parities
+            .iter()
+            .any(|parity| parity.parity_bit == nominal)
+        {
+            return Some(FieldRole::Parity);
+        }
+        self.field_at(second, minute_length).map(|field| field.role)
+    }
+}
+
+/// The ways to group the bits of a minute in the output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Grouping {
+    /// a group per field
+    #[default]
+    Fields,
+    /// no grouping at all
+    None,
+    /// groups of four bits
+    Nibbles,
+}
+
+impl FromStr for Grouping {
+    type Err = String;
+
+    /// Parse the name of the grouping, case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fields" => Ok(Grouping::Fields),
+            "none" => Ok(Grouping::None),
+            "nibbles" => Ok(Grouping::Nibbles),
+            _ => Err(format!(
+                "grouping must be 'fields', 'none' or 'nibbles' but is '{}'",
+                s.to_lowercase()
+            )),
+        }
+    }
+}
+
+/// Return the layout of a minute of the given station.
+///
+/// # Arguments
+/// * `station` - the station to return the layout for
+pub fn layout(station: Station) -> &'static Layout {
+    match station {
+        Station::Dcf77 => &dcf77::LAYOUT,
+        Station::Msf => &msf::LAYOUT,
+    }
+}
+
+/// Analyze a logfile of the given station, return the analysis of each minute.
+///
+/// # Arguments
+/// * `station` - the station which was logged
+/// * `buffer` - the buffer containing the logfile
+/// * `settings` - the settings of the analysis
+pub fn analyze_minutes(
+    station: Station,
+    buffer: &str,
+    settings: &AnalysisSettings,
+) -> Vec<MinuteReport> {
+    match station {
+        Station::Dcf77 => dcf77::analyze_minutes(buffer, settings),
+        Station::Msf => msf::analyze_minutes(buffer, settings),
+    }
+}
+
+/// Analyzer of a log which is fed as it arrives, e.g. from a receiver, and returns each minute
+/// once it is complete. Feeding a log in parts gives the same minutes as `analyze_minutes()`,
+/// without keeping the log.
+pub enum Analyzer {
+    Dcf77(dcf77::Analyzer),
+    Msf(Box<msf::Analyzer>),
+}
+
+impl Analyzer {
+    /// Return a new analyzer for the given station, at the start of a log.
+    ///
+    /// # Arguments
+    /// * `station` - the station which is logged
+    /// * `settings` - the settings of the analysis
+    pub fn new(station: Station, settings: &AnalysisSettings) -> Self {
+        match station {
+            Station::Dcf77 => Analyzer::Dcf77(dcf77::Analyzer::new(settings)),
+            Station::Msf => Analyzer::Msf(Box::new(msf::Analyzer::new(settings))),
+        }
+    }
+
+    /// Analyze the given part of the log, return the analysis of each minute completed by it.
+    ///
+    /// # Arguments
+    /// * `buffer` - the next part of the log
+    pub fn feed(&mut self, buffer: &str) -> Vec<MinuteReport> {
+        match self {
+            Analyzer::Dcf77(s_analyzer) => s_analyzer.feed(buffer),
+            Analyzer::Msf(s_analyzer) => s_analyzer.feed(buffer),
+        }
+    }
+
+    /// Return the analysis of the incomplete minute at the end of the log, if it has any output.
+    pub fn finish(self) -> Option<MinuteReport> {
+        match self {
+            Analyzer::Dcf77(s_analyzer) => s_analyzer.finish(),
+            Analyzer::Msf(s_analyzer) => (*s_analyzer).finish(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
     use std::ops::Add;
 
     pub(crate) fn parse_expected_log(exp_str: &str) -> Vec<String> {
@@ -13,4 +677,148 @@ mod tests {
             .map(|x| x.add("\n"))
             .collect()
     }
+
+    #[test]
+    fn test_decoded_time() {
+        let mut time = DecodedTime {
+            year: Some(11),
+            month: Some(3),
+            day: Some(27),
+            weekday: Some(7),
+            hour: Some(3),
+            minute: Some(0),
+            dst: None,
+            leap_second: None,
+            ..DecodedTime::default()
+        };
+        assert_eq!(time.minutes(), Some(5_908_500));
+        assert_eq!(str_time(&time), "11-03-27 03:00");
+        assert_eq!(time.unix_time(Station::Dcf77), None);
+        time.dst = Some(radio_datetime_utils::DST_SUMMER);
+        assert_eq!(time.unix_time(Station::Dcf77), Some(1_301_187_600));
+        assert_eq!(time.unix_time(Station::Msf), Some(1_301_191_200));
+        time.century_pivot = 11;
+        assert_eq!(time.unix_time(Station::Dcf77), Some(-1_854_572_400));
+        time.hour = None;
+        assert_eq!(time.minutes(), None);
+        assert_eq!(str_time(&time), "11-03-27 **:00");
+        time.hour = Some(24);
+        assert_eq!(time.minutes(), None);
+    }
+
+    #[test]
+    fn test_full_year() {
+        assert_eq!(full_year(24, 100), 2024);
+        assert_eq!(full_year(99, 100), 2099);
+        assert_eq!(full_year(89, 90), 2089);
+        assert_eq!(full_year(99, 90), 1999);
+        assert_eq!(full_year(0, 0), 1900);
+    }
+
+    #[test]
+    fn test_analyzer() {
+        for (station, log) in [
+            (
+                Station::Dcf77,
+                include_str!("transmitters/test/sample_dcf77.log"),
+            ),
+            (
+                Station::Msf,
+                include_str!("transmitters/test/sample_msf.log"),
+            ),
+        ] {
+            let settings = AnalysisSettings::default();
+            let mut analyzer = Analyzer::new(station, &settings);
+            // feed the log in parts which do not end at a minute marker
+            let mut minutes = Vec::new();
+            let chars: Vec<char> = log.chars().collect();
+            for part in chars.chunks(7) {
+                minutes.extend(analyzer.feed(&part.iter().collect::<String>()));
+            }
+            minutes.extend(analyzer.finish());
+            assert_eq!(minutes, analyze_minutes(station, log, &settings));
+        }
+    }
+
+    #[test]
+    fn test_layout_dcf77() {
+        let layout = layout(Station::Dcf77);
+        assert_eq!(layout.field_at(0, 60).unwrap().role, FieldRole::Marker);
+        assert_eq!(layout.field_at(17, 60).unwrap().name, "DST");
+        assert_eq!(layout.field_at(28, 60).unwrap().role, FieldRole::Parity);
+        assert_eq!(layout.field_at(59, 61).unwrap().name, "Leap second");
+        assert_eq!(layout.field_at(60, 61), None);
+    }
+
+    #[test]
+    fn test_layout_msf() {
+        let layout = layout(Station::Msf);
+        assert_eq!(layout.nominal_second(16, 60), 16);
+        assert_eq!(layout.nominal_second(17, 60), 17);
+        assert_eq!(layout.nominal_second(17, 61), 16);
+        assert_eq!(layout.nominal_second(18, 61), 17);
+        assert_eq!(layout.nominal_second(16, 59), 17);
+        assert_eq!(layout.field_at(17, 61).unwrap().name, "DUT1-");
+        assert_eq!(layout.field_at(60, 61).unwrap().name, "End-of-minute");
+        assert_eq!(layout.role_at(53, 60), Some(FieldRole::Marker));
+        assert_eq!(layout.role_at(54, 60), Some(FieldRole::Parity));
+        assert_eq!(layout.role_at(58, 61), Some(FieldRole::Parity));
+    }
+
+    #[test]
+    fn test_error_kind() {
+        for kind in ERROR_KINDS {
+            assert_eq!(kind.to_string().parse::<ErrorKind>(), Ok(kind));
+        }
+        assert_eq!(
+            "dst".parse::<ErrorKind>(),
+            Err(String::from(
+                "kind must be one of length, bits, parity, jump, leap but is 'dst'"
+            ))
+        );
+        let mut report = MinuteReport::default();
+        report.push("0 101", None);
+        report.push("Minute parity bad", Some(ErrorKind::Parity));
+        assert_eq!(
+            report.errors,
+            vec![MinuteError {
+                line: Some(1),
+                ..MinuteError::new(ErrorKind::Parity, "Minute parity bad")
+            }]
+        );
+        assert!(report.has_error(ErrorKind::Parity));
+        assert!(!report.has_error(ErrorKind::Jump));
+        assert_eq!(report.error_messages(), vec!["Minute parity bad"]);
+        assert_eq!(report.bits_line(), Some(0));
+    }
+
+    #[test]
+    fn test_str_grouped() {
+        const DCF77_BITS: &str = "00000000000000000010100011011110001110001110101001100110011";
+        let dcf77 = layout(Station::Dcf77);
+        assert_eq!(
+            dcf77.str_grouped(DCF77_BITS, Grouping::Fields),
+            "0 00000000000000 0 001 0 1 0001101 1 110001 1 100011 101 01001 10011001 1"
+        );
+        assert_eq!(dcf77.str_grouped(DCF77_BITS, Grouping::None), DCF77_BITS);
+        assert_eq!(
+            dcf77.str_grouped(&DCF77_BITS[..10], Grouping::Nibbles),
+            "0000 0000 00"
+        );
+        let msf = layout(Station::Msf);
+        // with a positive leap second
+        assert_eq!(
+            msf.str_grouped(
+                "4000000002200000000010000000011101000110100011101100101133110",
+                Grouping::Fields
+            ),
+            "4 00000000 220000000 00100000 00011 101000 110 100011 1011001 01133110"
+        );
+    }
+
+    #[test]
+    fn test_grouping_from_str() {
+        assert_eq!("Nibbles".parse::<Grouping>(), Ok(Grouping::Nibbles));
+        assert!("bytes".parse::<Grouping>().is_err());
+    }
 }
@@ -1,67 +1,338 @@
-use crate::{str_datetime, str_jumps, str_weekday};
+use crate::encoder::{bcd, MinuteInfo};
+use crate::leap_seconds::LeapSecondCheck;
+use crate::transmitters::{
+    AnalysisSettings, DecodedTime, ErrorKind, Field, FieldRole, Grouping, Layout, MinuteError,
+    MinuteReport, ParityGroup,
+};
+use crate::{jump_errors, str_datetime, str_weekday};
 use dcf77_utils::{DCF77Utils, DecodeType};
+use radio_datetime_utils::RadioDateTimeUtils;
+
+/// The fields of a DCF77 minute, the leap second is appended at the end.
+pub const LAYOUT: Layout = Layout {
+    fields: &FIELDS,
+    parities: &PARITIES,
+    leap_second_at: None,
+};
+
+const FIELDS: [Field; 16] = [
+    Field {
+        name: "Start of minute",
+        start: 0,
+        end: 1,
+        role: FieldRole::Marker,
+        weights: &[],
+        bit_names: &[],
+    },
+    Field {
+        name: "Third-party",
+        start: 1,
+        end: 15,
+        role: FieldRole::Data,
+        weights: &[],
+        bit_names: &[],
+    },
+    Field {
+        name: "Call bit",
+        start: 15,
+        end: 16,
+        role: FieldRole::Flag,
+        weights: &[],
+        bit_names: &[],
+    },
+    Field {
+        name: "DST",
+        start: 16,
+        end: 19,
+        role: FieldRole::Flag,
+        weights: &[],
+        bit_names: &["A1 (DST announcement)", "Z1 (CEST)", "Z2 (CET)"],
+    },
+    Field {
+        name: "Leap second announcement",
+        start: 19,
+        end: 20,
+        role: FieldRole::Flag,
+        weights: &[],
+        bit_names: &["A2 (leap second announcement)"],
+    },
+    Field {
+        name: "Start of time",
+        start: 20,
+        end: 21,
+        role: FieldRole::Marker,
+        weights: &[],
+        bit_names: &[],
+    },
+    Field {
+        name: "Minute",
+        start: 21,
+        end: 28,
+        role: FieldRole::Time,
+        weights: &[1, 2, 4, 8, 10, 20, 40],
+        bit_names: &[],
+    },
+    Field {
+        name: "Minute parity",
+        start: 28,
+        end: 29,
+        role: FieldRole::Parity,
+        weights: &[],
+        bit_names: &["P1"],
+    },
+    Field {
+        name: "Hour",
+        start: 29,
+        end: 35,
+        role: FieldRole::Time,
+        weights: &[1, 2, 4, 8, 10, 20],
+        bit_names: &[],
+    },
+    Field {
+        name: "Hour parity",
+        start: 35,
+        end: 36,
+        role: FieldRole::Parity,
+        weights: &[],
+        bit_names: &["P2"],
+    },
+    Field {
+        name: "Day-of-month",
+        start: 36,
+        end: 42,
+        role: FieldRole::Time,
+        weights: &[1, 2, 4, 8, 10, 20],
+        bit_names: &[],
+    },
+    Field {
+        name: "Day-of-week",
+        start: 42,
+        end: 45,
+        role: FieldRole::Time,
+        weights: &[1, 2, 4],
+        bit_names: &[],
+    },
+    Field {
+        name: "Month",
+        start: 45,
+        end: 50,
+        role: FieldRole::Time,
+        weights: &[1, 2, 4, 8, 10],
+        bit_names: &[],
+    },
+    Field {
+        name: "Year",
+        start: 50,
+        end: 58,
+        role: FieldRole::Time,
+        weights: &[1, 2, 4, 8, 10, 20, 40, 80],
+        bit_names: &[],
+    },
+    Field {
+        name: "Date parity",
+        start: 58,
+        end: 59,
+        role: FieldRole::Parity,
+        weights: &[],
+        bit_names: &["P3"],
+    },
+    Field {
+        name: "Leap second",
+        start: 59,
+        end: 60,
+        role: FieldRole::Marker,
+        weights: &[],
+        bit_names: &[],
+    },
+];
+
+const PARITIES: [ParityGroup; 3] = [
+    ParityGroup {
+        name: "Minute parity",
+        start: 21,
+        end: 28,
+        parity_bit: 28,
+    },
+    ParityGroup {
+        name: "Hour parity",
+        start: 29,
+        end: 35,
+        parity_bit: 35,
+    },
+    ParityGroup {
+        name: "Date parity",
+        start: 36,
+        end: 58,
+        parity_bit: 58,
+    },
+];
 
 /// Analyze a DCF77 logfile, return the input with the results interleaved.
 ///
 /// # Arguments
 /// `buffer` - the buffer containing the DCF77 logfile
-pub fn analyze_buffer(buffer: &str) -> Vec<String> {
-    let mut dcf77 = DCF77Utils::new(DecodeType::LogFile);
-    let mut res = Vec::new();
-    let mut bits = String::from("");
-    for c in buffer.chars() {
-        if !['0', '1', '_', '\n'].contains(&c) {
-            continue;
+/// `settings` - the settings of the analysis
+pub fn analyze_buffer(buffer: &str, settings: &AnalysisSettings) -> Vec<String> {
+    analyze_minutes(buffer, settings)
+        .into_iter()
+        .flat_map(|report| report.lines)
+        .collect()
+}
+
+/// Analyze a DCF77 logfile, return the analysis of each minute.
+///
+/// # Arguments
+/// `buffer` - the buffer containing the DCF77 logfile
+/// `settings` - the settings of the analysis
+pub fn analyze_minutes(buffer: &str, settings: &AnalysisSettings) -> Vec<MinuteReport> {
+    let mut analyzer = Analyzer::new(settings);
+    let mut res = analyzer.feed(buffer);
+    res.extend(analyzer.finish());
+    res
+}
+
+/// Analyzer of a DCF77 log which is fed as it arrives and returns each minute once it is
+/// complete, see `analyze_minutes()`.
+pub struct Analyzer {
+    dcf77: DCF77Utils,
+    leap_check: LeapSecondCheck,
+    century_pivot: u8,
+    /// the analysis of the minute being received
+    report: MinuteReport,
+    /// the bits of the minute being received, grouped for the output
+    bits: String,
+    /// the time of the last minute, decoded or counted on from the last decoded one
+    last_time: Option<RadioDateTimeUtils>,
+}
+
+impl Analyzer {
+    /// Return a new analyzer, at the start of a log.
+    ///
+    /// # Arguments
+    /// `settings` - the settings of the analysis
+    pub fn new(settings: &AnalysisSettings) -> Self {
+        Self {
+            dcf77: DCF77Utils::new(DecodeType::LogFile),
+            leap_check: LeapSecondCheck::new(
+                &settings.leap_seconds,
+                settings.century_pivot,
+                1,
+                true,
+            ),
+            century_pivot: settings.century_pivot,
+            report: MinuteReport::default(),
+            bits: String::from(""),
+            last_time: None,
         }
-        append_bit(&mut dcf77, c); // does nothing with newline
-        bits.push_str(&str_bit(&dcf77, c));
-        let actual_len = dcf77.get_second() + 1;
-        let wanted_len = dcf77.get_next_minute_length();
-        if c == '\n' {
-            res.push(bits.clone());
-            bits.clear();
-            if actual_len == wanted_len {
-                dcf77.decode_time(false);
-                let rdt = dcf77.get_radio_datetime();
-                res.push(format!(
-                    "first_minute={} seconds={} this_minute_length={} next_minute_length={}\n",
-                    dcf77.get_first_minute(),
-                    actual_len,
-                    dcf77.get_this_minute_length(),
-                    dcf77.get_next_minute_length()
-                ));
-                res.push(format!(
-                    "{} [{}] [{}]\n",
-                    str_datetime(&rdt, str_weekday(rdt.get_weekday(), 7), rdt.get_dst()),
-                    leap_second_info(rdt.get_leap_second(), dcf77.get_leap_second_is_one()),
-                    str_call_bit(&dcf77),
-                ));
-                res.push(format!(
-                    "Third-party buffer={}\n",
-                    str_hex(dcf77.get_third_party_buffer())
-                ));
-                for parity in str_parities(&dcf77) {
-                    res.push(format!("{parity}\n"));
-                }
-                for check in str_check_bits(&dcf77) {
-                    res.push(format!("{check}\n"));
-                }
-                for jump in str_jumps(&rdt) {
-                    res.push(format!("{jump}\n"));
+    }
+
+    /// Analyze the given part of the log, return the analysis of each minute completed by it.
+    ///
+    /// # Arguments
+    /// `buffer` - the next part of the DCF77 log
+    pub fn feed(&mut self, buffer: &str) -> Vec<MinuteReport> {
+        let dcf77 = &mut self.dcf77;
+        let mut res = Vec::new();
+        for c in buffer.chars() {
+            if !['0', '1', '_', '\n'].contains(&c) {
+                continue;
+            }
+            append_bit(dcf77, c); // does nothing with newline
+            self.bits.push_str(&str_bit(dcf77, c));
+            let actual_len = dcf77.get_second() + 1;
+            let wanted_len = dcf77.get_next_minute_length();
+            if c == '\n' {
+                let report = &mut self.report;
+                report.lines.push(self.bits.clone());
+                self.bits.clear();
+                if actual_len == wanted_len {
+                    dcf77.decode_time(false);
+                    let rdt = dcf77.get_radio_datetime();
+                    report.time = Some(DecodedTime::new(&rdt, self.century_pivot));
+                    report.push(
+                        &format!(
+                            "first_minute={} seconds={} this_minute_length={} next_minute_length={}",
+                            dcf77.get_first_minute(),
+                            actual_len,
+                            dcf77.get_this_minute_length(),
+                            dcf77.get_next_minute_length()
+                        ),
+                        None,
+                    );
+                    report.push(
+                        &format!(
+                            "{} [{}] [{}]",
+                            str_datetime(&rdt, str_weekday(rdt.get_weekday(), 7), rdt.get_dst()),
+                            leap_second_info(rdt.get_leap_second(), dcf77.get_leap_second_is_one()),
+                            str_call_bit(dcf77),
+                        ),
+                        None,
+                    );
+                    report.push(
+                        &format!(
+                            "Third-party buffer={}",
+                            str_hex(dcf77.get_third_party_buffer())
+                        ),
+                        None,
+                    );
+                    for parity in parity_errors(dcf77) {
+                        report.push_error(parity);
+                    }
+                    for check in str_check_bits(dcf77) {
+                        report.push(check, Some(ErrorKind::Bits));
+                    }
+                    for jump in jump_errors(&rdt) {
+                        report.push_error(jump);
+                    }
+                    for leap in self.leap_check.check(&rdt, actual_len) {
+                        report.push(&leap, Some(ErrorKind::Leap));
+                    }
+                    self.last_time = Some(rdt);
+                } else {
+                    report.push(
+                        &format!(
+                            "Minute is {actual_len} seconds instead of {wanted_len} seconds long"
+                        ),
+                        Some(ErrorKind::Length),
+                    );
+                    // an unannounced or missing leap second gives a minute of the wrong length,
+                    // so check it against the time counted on from the last decoded minute
+                    if let Some(s_time) = &mut self.last_time {
+                        if s_time.add_minute() {
+                            for leap in self.leap_check.check(s_time, actual_len) {
+                                report.push(&leap, Some(ErrorKind::Leap));
+                            }
+                        } else {
+                            self.last_time = None;
+                        }
+                    }
                 }
-            } else {
-                res.push(format!(
-                    "Minute is {actual_len} seconds instead of {wanted_len} seconds long\n"
+                dcf77.force_new_minute(); // (this, next) = (next, new_next)
+                report.lines.push(String::from("\n"));
+                let index = report.index;
+                res.push(std::mem::replace(
+                    report,
+                    MinuteReport {
+                        index: index + 1,
+                        ..MinuteReport::default()
+                    },
                 ));
+            } else {
+                self.report.bits.push(c);
+            }
+            if !dcf77.increase_second() {
+                self.report
+                    .push("increase_second() == false", Some(ErrorKind::Length));
+                // shown _before_ the bit buffer and analysis
             }
-            dcf77.force_new_minute(); // (this, next) = (next, new_next)
-            res.push(String::from("\n"));
-        }
-        if !dcf77.increase_second() {
-            res.push(String::from("increase_second() == false\n")); // shown _before_ the bit buffer and analysis
         }
+        res
+    }
+
+    /// Return the analysis of the incomplete minute at the end of the log, if it has any output.
+    pub fn finish(self) -> Option<MinuteReport> {
+        (!self.report.lines.is_empty()).then_some(self.report)
     }
-    res
 }
 
 /// Append the given bit to the current DCF77 structure
@@ -87,9 +358,7 @@ fn append_bit(dcf77: &mut DCF77Utils, c: char) {
 /// * `c` the bit to stringify
 fn str_bit(dcf77: &DCF77Utils, c: char) -> String {
     let mut bit = String::from("");
-    if c != '\n'
-        && [1, 15, 16, 19, 20, 21, 28, 29, 35, 36, 42, 45, 50, 58, 59].contains(&dcf77.get_second())
-    {
+    if c != '\n' && LAYOUT.starts_group(Grouping::Fields, dcf77.get_second() as usize, 60) {
         bit.push(' ');
     }
     bit.push(c);
@@ -143,26 +412,27 @@ fn leap_second_info(leap_second: Option<u8>, is_one: Option<bool>) -> String {
     s
 }
 
-/// Return a vector containing the parity values in plain English.
+/// Return a vector containing the bad or undetermined parities, in the order of `PARITIES`.
 ///
 /// # Arguments
 /// * `dcf77` - structure holding the currently decoded DCF77 data
-fn str_parities(dcf77: &DCF77Utils) -> Vec<&str> {
+fn parity_errors(dcf77: &DCF77Utils) -> Vec<MinuteError> {
     let mut parities = Vec::new();
-    if dcf77.get_parity_1() == Some(true) {
-        parities.push("Minute parity bad");
-    } else if dcf77.get_parity_1().is_none() {
-        parities.push("Minute parity undetermined");
-    }
-    if dcf77.get_parity_2() == Some(true) {
-        parities.push("Hour parity bad");
-    } else if dcf77.get_parity_2().is_none() {
-        parities.push("Hour parity undetermined");
-    }
-    if dcf77.get_parity_3() == Some(true) {
-        parities.push("Date parity bad");
-    } else if dcf77.get_parity_3().is_none() {
-        parities.push("Date parity undetermined");
+    let results = [
+        dcf77.get_parity_1(),
+        dcf77.get_parity_2(),
+        dcf77.get_parity_3(),
+    ];
+    for (idx, (parity, result)) in PARITIES.iter().zip(results).enumerate() {
+        match result {
+            // DCF77 uses even parity, a set result means it is bad
+            Some(true) => parities.push(MinuteError::bad_parity(idx, parity)),
+            Some(false) => {}
+            None => parities.push(MinuteError::new(
+                ErrorKind::Parity,
+                &format!("{} undetermined", parity.name),
+            )),
+        }
     }
     parities
 }
@@ -198,21 +468,91 @@ fn str_check_bits(dcf77: &DCF77Utils) -> Vec<&str> {
     checks
 }
 
+/// Return the line of bits which DCF77 broadcasts for the given minute, including the newline.
+///
+/// # Arguments
+/// * `info` - the information to encode
+pub fn encode_minute(info: &MinuteInfo) -> String {
+    let mut bits = vec![false];
+    bits.extend((0..14).map(|bit| info.third_party >> bit & 1 == 1));
+    bits.extend([
+        info.call_bit,
+        info.dst_announced,
+        info.summer,
+        !info.summer,
+        info.leap_announced,
+        true,
+    ]);
+    bits.extend(bcd(info.minute, &[1, 2, 4, 8, 10, 20, 40]));
+    bits.push(bits[21..28].iter().filter(|&&bit| bit).count() % 2 == 1);
+    bits.extend(bcd(info.hour, &[1, 2, 4, 8, 10, 20]));
+    bits.push(bits[29..35].iter().filter(|&&bit| bit).count() % 2 == 1);
+    bits.extend(bcd(info.day, &[1, 2, 4, 8, 10, 20]));
+    // Sunday is 7
+    bits.extend(bcd(
+        if info.weekday == 0 { 7 } else { info.weekday },
+        &[1, 2, 4],
+    ));
+    bits.extend(bcd(info.month, &[1, 2, 4, 8, 10]));
+    bits.extend(bcd(info.year, &[1, 2, 4, 8, 10, 20, 40, 80]));
+    bits.push(bits[36..58].iter().filter(|&&bit| bit).count() % 2 == 1);
+    match info.leap_second {
+        1 => bits.push(false),
+        -1 => {
+            bits.pop();
+        }
+        _ => {}
+    }
+    let mut res: String = bits
+        .iter()
+        .map(|&bit| if bit { '1' } else { '0' })
+        .collect();
+    res.push('\n');
+    res
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::encoder::{encode_log, EncoderSettings};
+    use crate::leap_seconds::LeapSecondTable;
     use crate::transmitters::tests::parse_expected_log;
+    use crate::Station;
     use dcf77_utils::DecodeType;
     use radio_datetime_utils::{LEAP_ANNOUNCED, LEAP_MISSING, LEAP_PROCESSED};
 
     #[test]
     fn test_analyze_logfile() {
         assert_eq!(
-            analyze_buffer(include_str!("test/sample_dcf77.log")),
+            analyze_buffer(
+                include_str!("test/sample_dcf77.log"),
+                &AnalysisSettings::default()
+            ),
             parse_expected_log(include_str!("test/expected_dcf77.txt"))
         );
     }
 
+    #[test]
+    fn test_analyze_missing_leap_second() {
+        // 2016-12-31 23:00 UTC until after the leap second, which is left out of the log
+        let log = encode_log(
+            Station::Dcf77,
+            1_483_225_200,
+            62,
+            &EncoderSettings::default(),
+            &LeapSecondTable::default(),
+        );
+        let mut lines: Vec<&str> = log.lines().collect();
+        lines[59] = &lines[59][..59];
+        let reports = analyze_minutes(&(lines.join("\n") + "\n"), &AnalysisSettings::default());
+        assert!(reports[59].has_error(ErrorKind::Length));
+        assert!(reports[59].errors.contains(&MinuteError::new(
+            ErrorKind::Leap,
+            "Minute is 60 seconds instead of 61 seconds long at scheduled leap second"
+        )));
+        assert!(!reports[58].has_error(ErrorKind::Leap));
+    }
+
     const LE_EMPTY: &str = "";
     const LE_ANN: &str = "announced";
     const LE_PROC: &str = "processed";
@@ -1,61 +1,298 @@
-use crate::{str_datetime, str_jumps, str_weekday};
+use crate::encoder::{bcd, MinuteInfo};
+use crate::leap_seconds::LeapSecondCheck;
+use crate::transmitters::{
+    AnalysisSettings, DecodedTime, ErrorKind, Field, FieldRole, Grouping, Layout, MinuteError,
+    MinuteReport, ParityGroup,
+};
+use crate::{jump_errors, str_datetime, str_weekday};
 use msf60_utils::MSFUtils;
-use std::cmp::Ordering;
+use radio_datetime_utils::RadioDateTimeUtils;
+
+/// The fields of a MSF minute, a leap second is inserted after second 16. The end-of-minute marker
+/// also contains the DST and parity bits in the B channel.
+pub const LAYOUT: Layout = Layout {
+    fields: &FIELDS,
+    parities: &PARITIES,
+    leap_second_at: Some(17),
+};
+
+const FIELDS: [Field; 10] = [
+    Field {
+        name: "Start of minute",
+        start: 0,
+        end: 1,
+        role: FieldRole::Marker,
+        weights: &[],
+        bit_names: &[],
+    },
+    Field {
+        name: "DUT1+",
+        start: 1,
+        end: 9,
+        role: FieldRole::Data,
+        weights: &[1, 1, 1, 1, 1, 1, 1, 1],
+        bit_names: &[],
+    },
+    Field {
+        name: "DUT1-",
+        start: 9,
+        end: 17,
+        role: FieldRole::Data,
+        weights: &[-1, -1, -1, -1, -1, -1, -1, -1],
+        bit_names: &[],
+    },
+    Field {
+        name: "Year",
+        start: 17,
+        end: 25,
+        role: FieldRole::Time,
+        weights: &[80, 40, 20, 10, 8, 4, 2, 1],
+        bit_names: &[],
+    },
+    Field {
+        name: "Month",
+        start: 25,
+        end: 30,
+        role: FieldRole::Time,
+        weights: &[10, 8, 4, 2, 1],
+        bit_names: &[],
+    },
+    Field {
+        name: "Day-of-month",
+        start: 30,
+        end: 36,
+        role: FieldRole::Time,
+        weights: &[20, 10, 8, 4, 2, 1],
+        bit_names: &[],
+    },
+    Field {
+        name: "Day-of-week",
+        start: 36,
+        end: 39,
+        role: FieldRole::Time,
+        weights: &[4, 2, 1],
+        bit_names: &[],
+    },
+    Field {
+        name: "Hour",
+        start: 39,
+        end: 45,
+        role: FieldRole::Time,
+        weights: &[20, 10, 8, 4, 2, 1],
+        bit_names: &[],
+    },
+    Field {
+        name: "Minute",
+        start: 45,
+        end: 52,
+        role: FieldRole::Time,
+        weights: &[40, 20, 10, 8, 4, 2, 1],
+        bit_names: &[],
+    },
+    Field {
+        name: "End-of-minute",
+        start: 52,
+        end: 60,
+        role: FieldRole::Marker,
+        weights: &[],
+        bit_names: &[
+            "End-of-minute 0",
+            "End-of-minute 1, B: DST warning",
+            "End-of-minute 1, B: Year parity",
+            "End-of-minute 1, B: Month/day-of-month parity",
+            "End-of-minute 1, B: Day-of-week parity",
+            "End-of-minute 1, B: Hour/minute parity",
+            "End-of-minute 1, B: summer time",
+            "End-of-minute 0",
+        ],
+    },
+];
+
+const PARITIES: [ParityGroup; 4] = [
+    ParityGroup {
+        name: "Year parity",
+        start: 17,
+        end: 25,
+        parity_bit: 54,
+    },
+    ParityGroup {
+        name: "Month/day-of-month parity",
+        start: 25,
+        end: 36,
+        parity_bit: 55,
+    },
+    ParityGroup {
+        name: "Day-of-week parity",
+        start: 36,
+        end: 39,
+        parity_bit: 56,
+    },
+    ParityGroup {
+        name: "Hour/minute parity",
+        start: 39,
+        end: 52,
+        parity_bit: 57,
+    },
+];
 
 /// Analyze a MSF logfile, return the input with the results interleaved.
 ///
 /// # Arguments
 /// `buffer` - the buffer containing the MSF logfile
-pub fn analyze_buffer(buffer: &str) -> Vec<String> {
-    let mut msf = MSFUtils::default();
-    let mut res = Vec::new();
-    let mut msf_buffer = [' '; radio_datetime_utils::BIT_BUFFER_SIZE];
-    for c in buffer.chars() {
-        if !['0', '1', '2', '3', '4', '_', '\n'].contains(&c) {
-            continue;
+/// `settings` - the settings of the analysis
+pub fn analyze_buffer(buffer: &str, settings: &AnalysisSettings) -> Vec<String> {
+    analyze_minutes(buffer, settings)
+        .into_iter()
+        .flat_map(|report| report.lines)
+        .collect()
+}
+
+/// Analyze a MSF logfile, return the analysis of each minute.
+///
+/// # Arguments
+/// `buffer` - the buffer containing the MSF logfile
+/// `settings` - the settings of the analysis
+pub fn analyze_minutes(buffer: &str, settings: &AnalysisSettings) -> Vec<MinuteReport> {
+    let mut analyzer = Analyzer::new(settings);
+    let mut res = analyzer.feed(buffer);
+    res.extend(analyzer.finish());
+    res
+}
+
+/// Analyzer of a MSF log which is fed as it arrives and returns each minute once it is
+/// complete, see `analyze_minutes()`.
+pub struct Analyzer {
+    msf: MSFUtils,
+    leap_check: LeapSecondCheck,
+    century_pivot: u8,
+    /// the analysis of the minute being received
+    report: MinuteReport,
+    /// the bits of the minute being received, for the output
+    msf_buffer: [char; radio_datetime_utils::BIT_BUFFER_SIZE],
+    /// the time of the last minute, decoded or counted on from the last decoded one
+    last_time: Option<RadioDateTimeUtils>,
+}
+
+impl Analyzer {
+    /// Return a new analyzer, at the start of a log.
+    ///
+    /// # Arguments
+    /// `settings` - the settings of the analysis
+    pub fn new(settings: &AnalysisSettings) -> Self {
+        Self {
+            msf: MSFUtils::default(),
+            // MSF does not announce leap seconds
+            leap_check: LeapSecondCheck::new(
+                &settings.leap_seconds,
+                settings.century_pivot,
+                0,
+                false,
+            ),
+            century_pivot: settings.century_pivot,
+            report: MinuteReport::default(),
+            msf_buffer: [' '; radio_datetime_utils::BIT_BUFFER_SIZE],
+            last_time: None,
         }
-        append_bits(&mut msf, c, &mut msf_buffer); // does nothing with newline except adding it to msf_buffer
-        let last_second = msf.get_second();
-        let wanted_len = msf.get_minute_length();
-        let eom = msf.end_of_minute_marker_present();
-        if c == '\n' {
-            if last_second + 1 == wanted_len {
-                res.push(str_bits(&msf_buffer, wanted_len));
-                msf.decode_time(false); // does not affect msf.get_minute_length()
-                let rdt = msf.get_radio_datetime();
-                res.push(format!(
-                    "first_minute={} seconds={} minute_length={}\n",
-                    msf.get_first_minute(),
-                    last_second + 1,
-                    wanted_len
-                ));
-                res.push(format!(
-                    "{} DUT1={}\n",
-                    str_datetime(&rdt, str_weekday(rdt.get_weekday(), 0), rdt.get_dst()),
-                    str_i8(msf.get_dut1())
-                ));
-                if !eom {
-                    res.push(String::from("End-of-minute marker absent\n"));
-                }
-                for parity in str_parities(&msf) {
-                    res.push(format!("{parity}\n"));
-                }
-                for jump in str_jumps(&rdt) {
-                    res.push(format!("{jump}\n"));
+    }
+
+    /// Analyze the given part of the log, return the analysis of each minute completed by it.
+    ///
+    /// # Arguments
+    /// `buffer` - the next part of the MSF log
+    pub fn feed(&mut self, buffer: &str) -> Vec<MinuteReport> {
+        let msf = &mut self.msf;
+        let mut res = Vec::new();
+        for c in buffer.chars() {
+            if !['0', '1', '2', '3', '4', '_', '\n'].contains(&c) {
+                continue;
+            }
+            append_bits(msf, c, &mut self.msf_buffer); // does nothing with newline except adding it to msf_buffer
+            let last_second = msf.get_second();
+            let wanted_len = msf.get_minute_length();
+            let eom = msf.end_of_minute_marker_present();
+            if c == '\n' {
+                let report = &mut self.report;
+                if last_second + 1 == wanted_len {
+                    report.lines.push(str_bits(&self.msf_buffer, wanted_len));
+                    msf.decode_time(false); // does not affect msf.get_minute_length()
+                    let rdt = msf.get_radio_datetime();
+                    report.time = Some(DecodedTime::new(&rdt, self.century_pivot));
+                    report.dut1 = msf.get_dut1();
+                    report.push(
+                        &format!(
+                            "first_minute={} seconds={} minute_length={}",
+                            msf.get_first_minute(),
+                            last_second + 1,
+                            wanted_len
+                        ),
+                        None,
+                    );
+                    report.push(
+                        &format!(
+                            "{} DUT1={}",
+                            str_datetime(&rdt, str_weekday(rdt.get_weekday(), 0), rdt.get_dst()),
+                            str_i8(msf.get_dut1())
+                        ),
+                        None,
+                    );
+                    if !eom {
+                        report.push("End-of-minute marker absent", Some(ErrorKind::Bits));
+                    }
+                    for parity in parity_errors(msf) {
+                        report.push_error(parity);
+                    }
+                    for jump in jump_errors(&rdt) {
+                        report.push_error(jump);
+                    }
+                    for leap in self.leap_check.check(&rdt, last_second + 1) {
+                        report.push(&leap, Some(ErrorKind::Leap));
+                    }
+                    self.last_time = Some(rdt);
+                } else {
+                    report.push(
+                        &format!(
+                            "Minute is {last_second} seconds instead of {wanted_len} seconds long"
+                        ),
+                        Some(ErrorKind::Length),
+                    );
+                    // such a minute is not decoded, so check the leap second table against the
+                    // time counted on from the last decoded minute
+                    if let Some(s_time) = &mut self.last_time {
+                        if s_time.add_minute() {
+                            for leap in self.leap_check.check(s_time, last_second + 1) {
+                                report.push(&leap, Some(ErrorKind::Leap));
+                            }
+                        } else {
+                            self.last_time = None;
+                        }
+                    }
                 }
-            } else {
-                res.push(format!(
-                    "Minute is {last_second} seconds instead of {wanted_len} seconds long\n"
+                msf.force_new_minute();
+                report.lines.push(String::from("\n"));
+                let index = report.index;
+                res.push(std::mem::replace(
+                    report,
+                    MinuteReport {
+                        index: index + 1,
+                        ..MinuteReport::default()
+                    },
                 ));
+            } else {
+                self.report.bits.push(c);
+            }
+            if !eom && !msf.increase_second() {
+                self.report
+                    .push("increase_second() == false", Some(ErrorKind::Length));
+                // shown _before_ the bit buffer and analysis
             }
-            msf.force_new_minute();
-            res.push(String::from("\n"));
-        }
-        if !eom && !msf.increase_second() {
-            res.push(String::from("increase_second() == false\n")); // shown _before_ the bit buffer and analysis
         }
+        res
+    }
+
+    /// Return the analysis of the incomplete minute at the end of the log, if it has any output.
+    pub fn finish(self) -> Option<MinuteReport> {
+        (!self.report.lines.is_empty()).then_some(self.report)
     }
-    res
 }
 
 /// Append the given bit pair to the current MSF structure and to the given buffer for later
@@ -64,7 +301,7 @@ pub fn analyze_buffer(buffer: &str) -> Vec<String> {
 /// # Arguments
 /// * `msf` - the structure to append the bit pair to
 /// * `c` - the bit pair to add. The newline is there for showing a new minute, it is a not
-///         a bit pair in itself.
+///   a bit pair in itself.
 /// * `buffer` - buffer storing the bits for later displaying
 fn append_bits(msf: &mut MSFUtils, c: char, buffer: &mut [char]) {
     if c != '\n' {
@@ -97,25 +334,8 @@ fn append_bits(msf: &mut MSFUtils, c: char, buffer: &mut [char]) {
 /// * `minute_length` - the number of bit pairs in this minute
 fn str_bits(buffer: &[char], minute_length: u8) -> String {
     let mut bits = String::from("");
-    let offset = match 60.cmp(&minute_length) {
-        Ordering::Less => 1,
-        Ordering::Equal => 0,
-        Ordering::Greater => -1,
-    };
     for (idx, c) in buffer.iter().enumerate() {
-        if [
-            1,
-            9,
-            17 + offset,
-            25 + offset,
-            30 + offset,
-            36 + offset,
-            39 + offset,
-            45 + offset,
-            52 + offset,
-        ]
-        .contains(&(idx as isize))
-        {
+        if *c != '\n' && LAYOUT.starts_group(Grouping::Fields, idx, minute_length as usize) {
             bits.push(' ');
         }
         bits.push(*c);
@@ -140,48 +360,133 @@ fn str_i8(value: Option<i8>) -> String {
     }
 }
 
-/// Return a vector containing the parity values in English.
+/// Return a vector containing the bad or undetermined parities, in the order of `PARITIES`.
 ///
 /// # Arguments
 /// * `msf` - structure holding the currently decoded MSF data
-fn str_parities(msf: &MSFUtils) -> Vec<&str> {
+fn parity_errors(msf: &MSFUtils) -> Vec<MinuteError> {
     let mut parities = Vec::new();
-    if msf.get_parity_1() == Some(false) {
-        parities.push("Year parity bad");
-    } else if msf.get_parity_1().is_none() {
-        parities.push("Year parity undetermined");
-    }
-    if msf.get_parity_2() == Some(false) {
-        parities.push("Month/day-of-month parity bad");
-    } else if msf.get_parity_2().is_none() {
-        parities.push("Month/day-of-month parity undetermined");
+    let results = [
+        msf.get_parity_1(),
+        msf.get_parity_2(),
+        msf.get_parity_3(),
+        msf.get_parity_4(),
+    ];
+    for (idx, (parity, result)) in PARITIES.iter().zip(results).enumerate() {
+        match result {
+            // MSF uses odd parity, a cleared result means it is bad
+            Some(false) => parities.push(MinuteError::bad_parity(idx, parity)),
+            Some(true) => {}
+            None => parities.push(MinuteError::new(
+                ErrorKind::Parity,
+                &format!("{} undetermined", parity.name),
+            )),
+        }
     }
-    if msf.get_parity_3() == Some(false) {
-        parities.push("Day-of-week parity bad");
-    } else if msf.get_parity_3().is_none() {
-        parities.push("Day-of-week parity undetermined");
+    parities
+}
+
+/// Return the line of bit pairs which MSF broadcasts for the given minute, including the newline.
+///
+/// # Arguments
+/// * `info` - the information to encode
+pub fn encode_minute(info: &MinuteInfo) -> String {
+    let mut bits_a = vec![false; 17];
+    let mut bits_b = vec![false; 17];
+    // positive DUT1 in seconds 1-8, negative DUT1 in seconds 9-16
+    let dut1_start = if info.dut1 >= 0 { 1 } else { 9 };
+    for bit in bits_b
+        .iter_mut()
+        .skip(dut1_start)
+        .take(info.dut1.unsigned_abs() as usize)
+    {
+        *bit = true;
     }
-    if msf.get_parity_4() == Some(false) {
-        parities.push("Hour/minute parity bad");
-    } else if msf.get_parity_4().is_none() {
-        parities.push("Hour/minute parity undetermined");
+    bits_a.extend(bcd(info.year, &[80, 40, 20, 10, 8, 4, 2, 1]));
+    bits_a.extend(bcd(info.month, &[10, 8, 4, 2, 1]));
+    bits_a.extend(bcd(info.day, &[20, 10, 8, 4, 2, 1]));
+    bits_a.extend(bcd(info.weekday, &[4, 2, 1]));
+    bits_a.extend(bcd(info.hour, &[20, 10, 8, 4, 2, 1]));
+    bits_a.extend(bcd(info.minute, &[40, 20, 10, 8, 4, 2, 1]));
+    bits_a.extend([false, true, true, true, true, true, true, false]);
+    // odd parities
+    let parity =
+        |from: usize, to: usize| bits_a[from..=to].iter().filter(|&&bit| bit).count() % 2 == 0;
+    bits_b.extend([false; 35]);
+    bits_b.extend([
+        false,
+        info.dst_announced,
+        parity(17, 24),
+        parity(25, 35),
+        parity(36, 38),
+        parity(39, 51),
+        info.summer,
+        false,
+    ]);
+    let mut res: Vec<char> = bits_a
+        .iter()
+        .zip(bits_b.iter())
+        .map(|bits| match bits {
+            (false, false) => '0',
+            (true, false) => '1',
+            (false, true) => '2',
+            (true, true) => '3',
+        })
+        .collect();
+    res[0] = '4';
+    // leap seconds are inserted or removed at second 16
+    match info.leap_second {
+        1 => res.insert(17, '0'),
+        -1 => {
+            res.remove(16);
+        }
+        _ => {}
     }
-    parities
+    res.push('\n');
+    res.iter().collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::encoder::{encode_log, EncoderSettings};
+    use crate::leap_seconds::LeapSecondTable;
     use crate::transmitters::tests::parse_expected_log;
+    use crate::Station;
 
     #[test]
     fn test_analyze_logfile() {
         assert_eq!(
-            analyze_buffer(include_str!("test/sample_msf.log")),
+            analyze_buffer(
+                include_str!("test/sample_msf.log"),
+                &AnalysisSettings::default()
+            ),
             parse_expected_log(include_str!("test/expected_msf.txt"))
         );
     }
 
+    #[test]
+    fn test_analyze_short_leap_second_minute() {
+        // 2016-12-31 23:00 UTC until after the leap second, the minute with it is cut short
+        let log = encode_log(
+            Station::Msf,
+            1_483_225_200,
+            62,
+            &EncoderSettings::default(),
+            &LeapSecondTable::default(),
+        );
+        let mut lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines[59].len(), 61);
+        lines[59] = &lines[59][..59];
+        let reports = analyze_minutes(&(lines.join("\n") + "\n"), &AnalysisSettings::default());
+        assert!(reports[59].has_error(ErrorKind::Length));
+        assert!(reports[59].errors.contains(&MinuteError::new(
+            ErrorKind::Leap,
+            "Minute is 59 seconds instead of 61 seconds long at scheduled leap second"
+        )));
+        assert!(!reports[58].has_error(ErrorKind::Leap));
+    }
+
     #[test]
     #[should_panic]
     fn test_append_bits_panic() {
@@ -0,0 +1,362 @@
+use crate::transmitters::{self, str_time, ErrorKind, MinuteReport, ERROR_KINDS};
+use crate::Station;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+/// Number of lines below the table of minutes, for the selected minute and the key help.
+const DETAIL_HEIGHT: usize = 22;
+
+/// State of the interactive browser over the analysis of a log.
+pub struct Browser<'a> {
+    station: Station,
+    reports: &'a [MinuteReport],
+    /// indices of the minutes which pass the filter
+    visible: Vec<usize>,
+    /// position of the selected minute in `visible`
+    selected: usize,
+    /// position in `visible` of the first minute shown in the table
+    scroll: usize,
+    /// only minutes with problems of this kind are shown, if any
+    filter: Option<ErrorKind>,
+}
+
+impl<'a> Browser<'a> {
+    /// Return a browser over the given minutes, with the first minute selected.
+    ///
+    /// # Arguments
+    /// * `station` - the station which was logged
+    /// * `reports` - the analysis of each minute of the log
+    pub fn new(station: Station, reports: &'a [MinuteReport]) -> Self {
+        Self {
+            station,
+            reports,
+            visible: (0..reports.len()).collect(),
+            selected: 0,
+            scroll: 0,
+            filter: None,
+        }
+    }
+
+    /// Return the index of the selected minute, if any minute is shown.
+    pub fn selected_minute(&self) -> Option<usize> {
+        self.visible.get(self.selected).copied()
+    }
+
+    /// Handle the given key, return false if the browser should quit.
+    ///
+    /// # Arguments
+    /// * `key` - the key which was pressed, the arrow keys are given as `k` and `j`
+    /// * `page` - the number of minutes to move for the page keys
+    pub fn handle_key(&mut self, key: char, page: usize) -> bool {
+        let last = self.visible.len().saturating_sub(1);
+        match key {
+            // Ctrl-C, as the terminal does not send a signal for it while browsing
+            'q' | '\x03' => return false,
+            'j' => self.selected = (self.selected + 1).min(last),
+            'k' => self.selected = self.selected.saturating_sub(1),
+            ' ' | 'd' => self.selected = (self.selected + page).min(last),
+            'u' => self.selected = self.selected.saturating_sub(page),
+            'g' => self.selected = 0,
+            'G' => self.selected = last,
+            'n' => {
+                if let Some(s_pos) = (self.selected + 1..self.visible.len())
+                    .find(|&pos| !self.reports[self.visible[pos]].errors.is_empty())
+                {
+                    self.selected = s_pos;
+                }
+            }
+            'p' => {
+                if let Some(s_pos) = (0..self.selected)
+                    .rev()
+                    .find(|&pos| !self.reports[self.visible[pos]].errors.is_empty())
+                {
+                    self.selected = s_pos;
+                }
+            }
+            'f' => {
+                // cycle through no filter and each kind of problem
+                self.filter = match self.filter {
+                    None => Some(ERROR_KINDS[0]),
+                    Some(s_kind) => ERROR_KINDS
+                        .iter()
+                        .position(|kind| *kind == s_kind)
+                        .and_then(|pos| ERROR_KINDS.get(pos + 1))
+                        .copied(),
+                };
+                self.apply_filter();
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Update the visible minutes for the current filter, keeping the selected minute if it is
+    /// still visible.
+    fn apply_filter(&mut self) {
+        let current = self.selected_minute();
+        self.visible = (0..self.reports.len())
+            .filter(|&idx| {
+                self.filter
+                    .is_none_or(|kind| self.reports[idx].has_error(kind))
+            })
+            .collect();
+        self.selected = current
+            .and_then(|idx| self.visible.iter().position(|&v_idx| v_idx >= idx))
+            .unwrap_or(0);
+        self.scroll = 0;
+    }
+
+    /// Return the screen contents for a terminal of the given height: a table of the minutes,
+    /// the bit layout of the selected minute and its problems, and the key help.
+    ///
+    /// # Arguments
+    /// * `height` - the number of lines of the terminal
+    pub fn render(&mut self, height: usize) -> String {
+        let rows = height.saturating_sub(DETAIL_HEIGHT + 1).max(1);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + rows {
+            self.scroll = self.selected + 1 - rows;
+        }
+        let mut res = String::from("  Minute Time           Status Problems\n");
+        for pos in self.scroll..self.visible.len().min(self.scroll + rows) {
+            let report = &self.reports[self.visible[pos]];
+            res += &format!(
+                "{} {:>6} {:<14} {:<6} {}\n",
+                if pos == self.selected { '>' } else { ' ' },
+                report.index + 1,
+                report
+                    .time
+                    .map_or(String::from("not decoded"), |time| str_time(&time)),
+                if report.is_good() { "ok" } else { "error" },
+                report.error_messages().join(", ")
+            );
+        }
+        res += &format!(
+            "-- {} of {} minutes, filter: {}\n",
+            self.visible.len(),
+            self.reports.len(),
+            self.filter
+                .map_or(String::from("none"), |kind| kind.to_string())
+        );
+        if let Some(s_idx) = self.selected_minute() {
+            res += &self.str_fields(&self.reports[s_idx]);
+        }
+        res += "j/k: move  d/u: page  g/G: first/last  n/p: next/previous problem  \
+                f: filter  q: quit\n";
+        res
+    }
+
+    /// Return the bits of each field of the given minute, followed by its problems.
+    ///
+    /// # Arguments
+    /// * `report` - the analysis of the minute
+    fn str_fields(&self, report: &MinuteReport) -> String {
+        let layout = transmitters::layout(self.station);
+        let minute_length = report.bits.chars().count();
+        let mut res = String::from("");
+        for field in layout.fields {
+            let bits: String = report
+                .bits
+                .chars()
+                .enumerate()
+                .filter(|&(second, _)| layout.field_at(second, minute_length) == Some(field))
+                .map(|(_, bit)| bit)
+                .collect();
+            if !bits.is_empty() {
+                res += &format!("{:>24}: {bits}\n", field.name);
+            }
+        }
+        for error in &report.errors {
+            res += &format!("{error}\n");
+        }
+        res
+    }
+}
+
+/// Run the interactive browser on the terminal until it is quit.
+///
+/// # Arguments
+/// * `station` - the station which was logged
+/// * `reports` - the analysis of each minute of the log
+pub fn run(station: Station, reports: &[MinuteReport]) -> Result<(), String> {
+    let _raw = RawMode::enter()?;
+    browse(station, reports)
+}
+
+/// The terminal in the mode for browsing, which restores the previous mode and clears the
+/// screen when dropped, also if browsing fails or panics.
+struct RawMode {
+    /// the previous mode as saved by `stty -g`
+    saved: String,
+}
+
+impl RawMode {
+    /// Save the mode of the terminal and switch to reading single keys without echo, with
+    /// Ctrl-C and the other signal keys read as keys as well.
+    fn enter() -> Result<Self, String> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        Ok(Self {
+            saved: String::from(saved.trim()),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // nothing left to report an error to
+        let _ = stty(&[&self.saved]);
+        print!("\x1b[2J\x1b[H");
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// Show the browser and handle keys until it is quit.
+///
+/// # Arguments
+/// * `station` - the station which was logged
+/// * `reports` - the analysis of each minute of the log
+fn browse(station: Station, reports: &[MinuteReport]) -> Result<(), String> {
+    let mut browser = Browser::new(station, reports);
+    let mut stdin = std::io::stdin().lock();
+    let mut escape = String::from("");
+    loop {
+        let height = stty(&["size"])
+            .ok()
+            .and_then(|size| size.split_whitespace().next()?.parse::<usize>().ok())
+            .unwrap_or(24);
+        print!("\x1b[2J\x1b[H{}", browser.render(height));
+        std::io::stdout()
+            .flush()
+            .map_err(|error| error.to_string())?;
+        let mut key = [0];
+        if stdin.read(&mut key).map_err(|error| error.to_string())? == 0 {
+            return Ok(());
+        }
+        let mut key = key[0] as char;
+        // arrow keys are sent as ESC [ A or ESC [ B
+        if key == '\x1b' || !escape.is_empty() {
+            escape.push(key);
+            match escape.as_str() {
+                "\x1b" | "\x1b[" => continue,
+                "\x1b[A" => key = 'k',
+                "\x1b[B" => key = 'j',
+                _ => {}
+            }
+            escape.clear();
+        }
+        if !browser.handle_key(key, height.saturating_sub(DETAIL_HEIGHT + 1).max(1)) {
+            return Ok(());
+        }
+    }
+}
+
+/// Run stty on the terminal with the given arguments, return its output.
+///
+/// # Arguments
+/// * `args` - the arguments for stty
+fn stty(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .map_err(|error| format!("Could not run stty : {error}"))?;
+    if !output.status.success() {
+        return Err(String::from(
+            "Could not set up the terminal, is input a terminal?",
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::{DecodedTime, MinuteError};
+
+    fn reports() -> Vec<MinuteReport> {
+        (0..6)
+            .map(|idx| MinuteReport {
+                index: idx,
+                bits: String::from("00000000000000000010100011011110001110001110101001100110011"),
+                time: Some(DecodedTime {
+                    year: Some(0),
+                    month: Some(1),
+                    day: Some(1),
+                    hour: Some(0),
+                    minute: Some(idx as u8),
+                    ..DecodedTime::default()
+                }),
+                errors: match idx {
+                    2 => vec![MinuteError::new(ErrorKind::Parity, "Hour parity bad")],
+                    4 => vec![MinuteError::new(ErrorKind::Jump, "Minute jumped")],
+                    _ => Vec::new(),
+                },
+                ..MinuteReport::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_handle_key() {
+        let reports = reports();
+        let mut browser = Browser::new(Station::Dcf77, &reports);
+        assert_eq!(browser.selected_minute(), Some(0));
+        browser.handle_key('k', 3);
+        assert_eq!(browser.selected_minute(), Some(0));
+        browser.handle_key('n', 3);
+        assert_eq!(browser.selected_minute(), Some(2));
+        browser.handle_key('n', 3);
+        assert_eq!(browser.selected_minute(), Some(4));
+        browser.handle_key('n', 3);
+        assert_eq!(browser.selected_minute(), Some(4));
+        browser.handle_key('p', 3);
+        assert_eq!(browser.selected_minute(), Some(2));
+        browser.handle_key('d', 3);
+        assert_eq!(browser.selected_minute(), Some(5));
+        browser.handle_key('j', 3);
+        assert_eq!(browser.selected_minute(), Some(5));
+        browser.handle_key('g', 3);
+        assert_eq!(browser.selected_minute(), Some(0));
+        assert!(!browser.handle_key('q', 3));
+        assert!(!browser.handle_key('\x03', 3));
+    }
+
+    #[test]
+    fn test_filter() {
+        let reports = reports();
+        let mut browser = Browser::new(Station::Dcf77, &reports);
+        browser.handle_key('j', 3);
+        browser.handle_key('f', 3); // length
+        assert_eq!(browser.selected_minute(), None);
+        browser.handle_key('f', 3); // bits
+        browser.handle_key('f', 3); // parity
+        assert_eq!(browser.selected_minute(), Some(2));
+        browser.handle_key('j', 3);
+        assert_eq!(browser.selected_minute(), Some(2));
+        browser.handle_key('f', 3); // jump
+        assert_eq!(browser.selected_minute(), Some(4));
+        browser.handle_key('f', 3); // leap
+        browser.handle_key('f', 3); // none
+        assert_eq!(browser.selected_minute(), Some(0));
+        assert_eq!(browser.visible.len(), 6);
+    }
+
+    #[test]
+    fn test_render() {
+        let reports = reports();
+        let mut browser = Browser::new(Station::Dcf77, &reports);
+        browser.handle_key('G', 3);
+        let screen = browser.render(DETAIL_HEIGHT + 4);
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[0], "  Minute Time           Status Problems");
+        assert_eq!(lines[1], "       4 00-01-01 00:03 ok     ");
+        assert_eq!(lines[2], "       5 00-01-01 00:04 error  Minute jumped");
+        assert_eq!(lines[3], ">      6 00-01-01 00:05 ok     ");
+        assert_eq!(lines[4], "-- 6 of 6 minutes, filter: none");
+        assert_eq!(lines[5], "         Start of minute: 0");
+        assert_eq!(lines[11], "                  Minute: 0001101");
+        assert!(lines.last().unwrap().starts_with("j/k: move"));
+    }
+}
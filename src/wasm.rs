@@ -0,0 +1,164 @@
+use crate::leap_seconds::LeapSecondTable;
+use crate::transmitters::{self, AnalysisSettings, MinuteReport};
+use crate::Station;
+use wasm_bindgen::prelude::*;
+
+/// Analyze a log of the given station and return the analysis as a JSON array with an object
+/// per minute, with the decoded fields (null if not decoded), the problems and the lines of the
+/// text output of the command line. Only the analysis itself is used, which does not need a file
+/// system, so this works in a browser.
+///
+/// # Arguments
+/// * `station` - the station which was logged, `dcf77` or `msf`
+/// * `buffer` - the contents of the log file
+/// * `leap_seconds` - the contents of a `leap-seconds.list` file, undefined for the built-in
+///   table
+/// * `century_pivot` - the year within the century from which years are in the 1900s, e.g. 90
+///   for logs from the 1990s, undefined for all years in the 2000s
+#[wasm_bindgen]
+pub fn analyze(
+    station: &str,
+    buffer: &str,
+    leap_seconds: Option<String>,
+    century_pivot: Option<u8>,
+) -> Result<String, JsError> {
+    let station = station
+        .parse::<Station>()
+        .map_err(|error| JsError::new(&error))?;
+    let leap_seconds = match leap_seconds {
+        Some(s_list) => LeapSecondTable::from_leap_seconds_list(&s_list)
+            .map_err(|error| JsError::new(&format!("Invalid leap second table : {error}")))?,
+        None => LeapSecondTable::default(),
+    };
+    let century_pivot = century_pivot.unwrap_or(100);
+    if century_pivot > 100 {
+        return Err(JsError::new(&format!(
+            "century_pivot must be in 0..=100 but is '{century_pivot}'"
+        )));
+    }
+    let settings = AnalysisSettings {
+        leap_seconds,
+        century_pivot,
+    };
+    Ok(to_json(
+        station,
+        &transmitters::analyze_minutes(station, buffer, &settings),
+    ))
+}
+
+/// Return the given minutes as a JSON array, see `analyze()`.
+///
+/// # Arguments
+/// * `station` - the station which was received, for the UTC time
+/// * `reports` - the analysis of the minutes
+fn to_json(station: Station, reports: &[MinuteReport]) -> String {
+    let minutes: Vec<_> = reports
+        .iter()
+        .map(|report| {
+            let fields: String = report
+                .fields(station)
+                .iter()
+                .map(|(name, value)| {
+                    format!(
+                        "\"{name}\":{},",
+                        value.map_or(String::from("null"), |v| v.to_string())
+                    )
+                })
+                .collect();
+            let strings = |values: &[&str]| {
+                let quoted: Vec<_> = values.iter().map(|value| quote(value)).collect();
+                format!("[{}]", quoted.join(","))
+            };
+            let lines: Vec<_> = report.lines.iter().map(|line| line.trim_end()).collect();
+            format!(
+                "{{\"index\":{},\"bits\":{},\"good\":{},{fields}\"errors\":{},\"details\":{},\
+                 \"lines\":{}}}",
+                report.index,
+                quote(&report.bits),
+                report.is_good(),
+                strings(&report.error_messages()),
+                strings(&report.details()),
+                strings(&lines)
+            )
+        })
+        .collect();
+    format!("[{}]", minutes.join(","))
+}
+
+/// Return the given text as a JSON string.
+///
+/// # Arguments
+/// * `text` - the text to quote
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c < ' ' => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted + "\""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitters::{DecodedTime, ErrorKind, MinuteError};
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("a \"b\"\\\n\t"), "\"a \\\"b\\\"\\\\\\n\\u0009\"");
+    }
+
+    #[test]
+    fn test_to_json() {
+        let reports = [
+            MinuteReport {
+                index: 0,
+                bits: String::from("0101"),
+                lines: vec![
+                    String::from("0101\n"),
+                    String::from("Minute is 4 seconds instead of 60 seconds long\n"),
+                ],
+                errors: vec![MinuteError {
+                    line: Some(1),
+                    ..MinuteError::new(
+                        ErrorKind::Length,
+                        "Minute is 4 seconds instead of 60 seconds long",
+                    )
+                }],
+                ..MinuteReport::default()
+            },
+            MinuteReport {
+                index: 1,
+                time: Some(DecodedTime {
+                    year: Some(24),
+                    month: Some(3),
+                    day: Some(31),
+                    weekday: Some(0),
+                    hour: Some(1),
+                    minute: Some(0),
+                    dst: Some(0),
+                    leap_second: Some(0),
+                    ..DecodedTime::default()
+                }),
+                dut1: Some(-2),
+                ..MinuteReport::default()
+            },
+        ];
+        assert_eq!(
+            to_json(Station::Msf, &reports),
+            "[{\"index\":0,\"bits\":\"0101\",\"good\":false,\"year\":null,\"month\":null,\
+             \"day\":null,\"weekday\":null,\"hour\":null,\"minute\":null,\"dst\":null,\
+             \"leap_second\":null,\"dut1\":null,\"utc\":null,\
+             \"errors\":[\"Minute is 4 seconds instead of 60 seconds long\"],\"details\":[],\
+             \"lines\":[\"0101\",\"Minute is 4 seconds instead of 60 seconds long\"]},\
+             {\"index\":1,\"bits\":\"\",\"good\":true,\"year\":24,\"month\":3,\"day\":31,\
+             \"weekday\":0,\"hour\":1,\"minute\":0,\"dst\":0,\"leap_second\":0,\"dut1\":-2,\
+             \"utc\":1711846800,\"errors\":[],\"details\":[],\"lines\":[]}]"
+        );
+    }
+}
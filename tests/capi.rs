@@ -0,0 +1,36 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+/// Build tests/capi_test.c against the C library and the committed header, and run it.
+#[cfg(target_os = "linux")]
+#[test]
+fn test_c_program() {
+    // the test runs from target/<profile>/deps, the library is in target/<profile>
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap().parent().unwrap();
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let program = exe.with_file_name("capi_test");
+    let status = Command::new(env::var("CC").unwrap_or(String::from("cc")))
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg(manifest_dir.join("tests/capi_test.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg("-L")
+        .arg(lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lradio_datetime_analyzer")
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .expect("Could not run the C compiler");
+    assert!(status.success());
+    let output = Command::new(&program).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
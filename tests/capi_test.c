@@ -0,0 +1,61 @@
+/* Test of the C interface, built and run by tests/capi.rs. */
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "radio_datetime_analyzer.h"
+
+/* Feed the given text to the analyzer. */
+static void feed(RdaAnalyzer *analyzer, const char *text)
+{
+	assert(rda_analyzer_feed(analyzer, (const uint8_t *)text, strlen(text)) == 0);
+}
+
+int main(void)
+{
+	RdaAnalyzer *analyzer;
+	RdaMinute minute;
+
+	assert(rda_analyzer_new(2) == NULL);
+	rda_analyzer_free(NULL);
+
+	analyzer = rda_analyzer_new(RDA_STATION_DCF77);
+	assert(analyzer != NULL);
+	assert(rda_analyzer_next(analyzer, &minute) == 0);
+	assert(rda_analyzer_set_leap_seconds(analyzer, "invalid\n") == -1);
+	assert(rda_analyzer_set_century_pivot(analyzer, 101) == -1);
+	assert(rda_analyzer_set_century_pivot(analyzer, 100) == 0);
+
+	/* a short minute, Sunday 2024-03-31 01:51 CET and one still being received */
+	feed(analyzer, "0101\n0000000000000000101011000101110000011000111");
+	feed(analyzer, "1111000001001000\n00000");
+	assert(rda_analyzer_next(analyzer, &minute) == 1);
+	assert(minute.index == 0);
+	assert(!minute.good);
+	assert(minute.year == RDA_UNKNOWN);
+	assert(strcmp(minute.bits, "0101") == 0);
+	assert(minute.error_count > 0);
+	assert(minute.errors[strlen(minute.errors) - 1] == '\n');
+
+	assert(rda_analyzer_next(analyzer, &minute) == 1);
+	assert(minute.index == 1);
+	assert(strlen(minute.bits) == 59);
+	printf("minute %zu: %s %s", minute.index, minute.bits, minute.good ? "good\n" : minute.errors);
+	/* shown by tests/capi.rs if an assertion below fails */
+	fflush(stdout);
+	assert(minute.year == 24);
+	assert(minute.month == 3);
+	assert(minute.day == 31);
+	assert(minute.weekday == 7);
+	assert(minute.hour == 1);
+	assert(minute.minute == 51);
+	assert(minute.dst != RDA_UNKNOWN);
+	assert(minute.dut1 == RDA_UNKNOWN);
+	assert(minute.error_count == 0);
+	assert(minute.good);
+
+	assert(rda_analyzer_next(analyzer, &minute) == 0);
+	assert(rda_analyzer_next(NULL, &minute) == -1);
+	rda_analyzer_free(analyzer);
+	return 0;
+}
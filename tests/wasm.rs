@@ -0,0 +1,33 @@
+// Run with `cargo test --target wasm32-unknown-unknown --no-default-features --features wasm
+// --test wasm`, which needs `wasm-bindgen-test-runner` from wasm-bindgen-cli and Node.js.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use radio_datetime_analyzer::wasm::analyze;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn test_analyze_sample_logs() {
+    for (station, log) in [
+        (
+            "dcf77",
+            include_str!("../src/transmitters/test/sample_dcf77.log"),
+        ),
+        (
+            "msf",
+            include_str!("../src/transmitters/test/sample_msf.log"),
+        ),
+    ] {
+        let json = analyze(station, log, None, None).unwrap();
+        assert!(json.starts_with("[{\"index\":0,"));
+        assert_eq!(
+            json.matches("\"index\":").count(),
+            log.matches('\n').count()
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_analyze_invalid() {
+    assert!(analyze("wwvb", "", None, None).is_err());
+    assert!(analyze("dcf77", "", Some(String::from("invalid")), None).is_err());
+}